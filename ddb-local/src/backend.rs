@@ -1,67 +1,194 @@
 use crate::DynamoDb;
+use crate::clock::{Clock, SystemClock};
+use crate::expression::{
+    attribute_updates_to_actions, compare_values, evaluate_condition_expression,
+    parse_update_expression, project, projection,
+};
+use crate::fixture::Fixture;
+use crate::item::{Item, dynamodb_item_size};
+use crate::recording::{Operation, Outcome, RecordingHandle};
+use crate::snapshot::StoreSnapshot;
 use aws_sdk_dynamodb::Client;
-use dynamodb_local_server_sdk::{error, input, output};
+use aws_smithy_types::DateTime;
+use bigdecimal::BigDecimal;
+use dynamodb_local_server_sdk::{error, input, model, output};
+use futures_util::Stream;
+use std::cmp::Ordering;
 use std::collections::{HashMap, hash_map::Entry};
+use std::fmt;
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
-fn evaluate_condition_expression(
-    expression: &str,
-    item: Option<&HashMap<String, dynamodb_local_server_sdk::model::AttributeValue>>,
-    expression_attribute_values: Option<
-        &HashMap<String, dynamodb_local_server_sdk::model::AttributeValue>,
-    >,
-) -> bool {
-    let expr = expression.trim();
-
-    // Handle AND expressions
-    if expr.contains(" AND ") {
-        return expr.split(" AND ").all(|sub_expr| {
-            evaluate_condition_expression(sub_expr.trim(), item, expression_attribute_values)
-        });
+fn validation_error(message: impl Into<String>) -> error::ValidationException {
+    error::ValidationException {
+        message: message.into(),
+        field_list: None,
     }
+}
 
-    // Handle OR expressions
-    if expr.contains(" OR ") {
-        return expr.split(" OR ").any(|sub_expr| {
-            evaluate_condition_expression(sub_expr.trim(), item, expression_attribute_values)
-        });
+/// A key schema must name a partition key and at most one sort key
+fn validate_key_schema_length(len: usize) -> Result<(), error::ValidationException> {
+    if (1..=2).contains(&len) {
+        Ok(())
+    } else {
+        Err(validation_error("KeySchema must have 1 or 2 elements"))
     }
+}
 
-    // Handle attribute_not_exists(attr)
-    if let Some(attr_start) = expr.find("attribute_not_exists(") {
-        let attr_end = expr[attr_start..].find(')').unwrap() + attr_start;
-        let attr_name = &expr[attr_start + 21..attr_end];
-        return item.map_or(true, |i| !i.contains_key(attr_name));
+/// The error DynamoDB returns for a table that doesn't exist, with its wording
+fn table_not_found(table_name: &str) -> error::ResourceNotFoundException {
+    error::ResourceNotFoundException::builder()
+        .message(Some(format!(
+            "Requested resource not found: Table: {table_name} not found"
+        )))
+        .build()
+}
+
+/// Check that `ProvisionedThroughput` matches the table's `BillingMode`
+///
+/// `PROVISIONED` needs positive read and write capacity and `PAY_PER_REQUEST`
+/// forbids it. Without an explicit billing mode anything goes, so tables
+/// created by tools that omit both keep working.
+fn validate_billing_mode(input: &input::CreateTableInput) -> Result<(), String> {
+    match (&input.billing_mode, &input.provisioned_throughput) {
+        (Some(model::BillingMode::Provisioned), None) => Err(
+            "One or more parameter values were invalid: ReadCapacityUnits and WriteCapacityUnits must both be specified when BillingMode is PROVISIONED"
+                .to_string(),
+        ),
+        (Some(model::BillingMode::PayPerRequest), Some(_)) => Err(
+            "One or more parameter values were invalid: Neither ReadCapacityUnits nor WriteCapacityUnits can be specified when BillingMode is PAY_PER_REQUEST"
+                .to_string(),
+        ),
+        (_, Some(throughput)) => [
+            ("readCapacityUnits", throughput.read_capacity_units),
+            ("writeCapacityUnits", throughput.write_capacity_units),
+        ]
+        .into_iter()
+        .find(|(_, units)| *units < 1)
+        .map_or(Ok(()), |(field, units)| {
+            Err(format!(
+                "1 validation error detected: Value '{units}' at 'provisionedThroughput.{field}' failed to satisfy constraint: Member must have value greater than or equal to 1"
+            ))
+        }),
+        (_, None) => Ok(()),
     }
+}
 
-    // Handle attribute_exists(attr)
-    if let Some(attr_start) = expr.find("attribute_exists(") {
-        let attr_end = expr[attr_start..].find(')').unwrap() + attr_start;
-        let attr_name = &expr[attr_start + 17..attr_end];
-        return item.map_or(false, |i| i.contains_key(attr_name));
+/// The attributes a write returns for its `ReturnValues` setting
+///
+/// `NONE`, which is also what an absent setting means, never returns data no
+/// matter what the old and new items hold. `updated` lists the attributes the
+/// write touched, for the `UPDATED_*` modes.
+fn returned_attributes(
+    return_values: Option<&model::ReturnValue>,
+    old: Option<&HashMap<String, model::AttributeValue>>,
+    new: Option<&HashMap<String, model::AttributeValue>>,
+    updated: &[&str],
+) -> Option<HashMap<String, model::AttributeValue>> {
+    let only_updated = |item: &HashMap<String, model::AttributeValue>| {
+        let item: HashMap<_, _> = item
+            .iter()
+            .filter(|(name, _)| updated.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        (!item.is_empty()).then_some(item)
+    };
+    match return_values {
+        None | Some(model::ReturnValue::None) => None,
+        Some(model::ReturnValue::AllOld) => old.cloned(),
+        Some(model::ReturnValue::AllNew) => new.cloned(),
+        Some(model::ReturnValue::UpdatedOld) => old.and_then(only_updated),
+        Some(model::ReturnValue::UpdatedNew) => new.and_then(only_updated),
     }
+}
 
-    // Handle equality: attr = :val
-    if let Some(eq_pos) = expr.find(" = ") {
-        let attr_name = expr[..eq_pos].trim();
-        let value_ref = expr[eq_pos + 3..].trim();
+/// Item operation that can be made to fail with [`InMemoryDynamoDb::fail_table`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    GetItem,
+    PutItem,
+    UpdateItem,
+}
 
-        if let (Some(item), Some(values)) = (item, expression_attribute_values) {
-            if let (Some(item_value), Some(expected_value)) =
-                (item.get(attr_name), values.get(value_ref))
-            {
-                return item_value == expected_value;
+/// Error returned by an operation failed with [`InMemoryDynamoDb::fail_table`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    InternalServerError,
+    ProvisionedThroughputExceeded,
+    ResourceNotFound,
+}
+
+/// Error returned when [`InMemoryDynamoDb::wait_for`] gives up
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaitForError {
+    /// No matching item appeared within the given duration
+    Timeout(Duration),
+    /// The key doesn't fit the table's key schema, so no item could ever match
+    InvalidKey(error::ValidationException),
+}
+
+impl fmt::Display for WaitForError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitForError::Timeout(timeout) => {
+                write!(f, "item did not match within {timeout:?}")
             }
+            WaitForError::InvalidKey(e) => write!(f, "invalid key: {e}"),
         }
-        return false;
     }
+}
+
+impl std::error::Error for WaitForError {}
 
-    false
+/// Turn an injected [`ErrorKind`] into the error type of one operation
+macro_rules! injected_error {
+    ($kind:expr, $table_name:expr, $error:ident) => {{
+        let message = Some(format!("Injected failure for table {}", $table_name));
+        match $kind {
+            ErrorKind::InternalServerError => error::$error::InternalServerError(
+                error::InternalServerError::builder()
+                    .message(message)
+                    .build(),
+            ),
+            ErrorKind::ProvisionedThroughputExceeded => {
+                error::$error::ProvisionedThroughputExceededException(
+                    error::ProvisionedThroughputExceededException::builder()
+                        .message(message)
+                        .build(),
+                )
+            }
+            ErrorKind::ResourceNotFound => error::$error::ResourceNotFoundException(
+                error::ResourceNotFoundException::builder()
+                    .message(message)
+                    .build(),
+            ),
+        }
+    }};
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct InMemoryDynamoDb {
     store: Arc<Mutex<HashMap<String, TableStore>>>,
+    clock: Arc<dyn Clock>,
+    strict: bool,
+    idempotent_create_table: bool,
+    items_per_table: usize,
+    failures: Arc<Mutex<HashMap<(String, OperationKind), ErrorKind>>>,
+    recording: Arc<Mutex<Option<RecordingHandle>>>,
+}
+
+impl Default for InMemoryDynamoDb {
+    fn default() -> Self {
+        Self {
+            store: Default::default(),
+            clock: Arc::new(SystemClock),
+            strict: false,
+            idempotent_create_table: false,
+            items_per_table: 0,
+            failures: Default::default(),
+            recording: Default::default(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -85,7 +212,8 @@ impl TestBackend {
                 .in_memory
                 .as_ref()
                 .unwrap()
-                .create_table(table_name, key_schema),
+                .create_table(table_name, key_schema)
+                .unwrap(),
             TestBackendType::DynamoDbLocal => {
                 // External DynamoDB Local handles table creation via client
             }
@@ -133,6 +261,32 @@ pub async fn create_test_client(backend_type: TestBackendType) -> (Client, TestB
     }
 }
 
+/// A key schema element for building `CreateTable` requests in tests
+#[cfg(test)]
+pub fn key_schema_element(
+    name: &str,
+    key_type: aws_sdk_dynamodb::types::KeyType,
+) -> aws_sdk_dynamodb::types::KeySchemaElement {
+    aws_sdk_dynamodb::types::KeySchemaElement::builder()
+        .attribute_name(name)
+        .key_type(key_type)
+        .build()
+        .unwrap()
+}
+
+/// An attribute definition for building `CreateTable` requests in tests
+#[cfg(test)]
+pub fn attribute_definition(
+    name: &str,
+    attribute_type: aws_sdk_dynamodb::types::ScalarAttributeType,
+) -> aws_sdk_dynamodb::types::AttributeDefinition {
+    aws_sdk_dynamodb::types::AttributeDefinition::builder()
+        .attribute_name(name)
+        .attribute_type(attribute_type)
+        .build()
+        .unwrap()
+}
+
 pub async fn create_in_memory_dynamodb_client() -> (Client, InMemoryDynamoDb) {
     let backend = InMemoryDynamoDb::new();
     let bound = crate::DynamoDbLocal::builder()
@@ -148,17 +302,414 @@ impl InMemoryDynamoDb {
         Self::default()
     }
 
-    pub fn create_table(&self, table_name: &str, key_schema: &[&str]) {
+    /// Use `clock` as the source of "now" (e.g. for table creation times)
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Only allow tables to be created through the `CreateTable` operation
+    ///
+    /// In strict mode [`create_table`](Self::create_table) always fails, which
+    /// catches tests that accidentally bypass the emulated API.
+    pub fn with_strict_mode(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Let `CreateTable` succeed for a table that already exists with the
+    /// same key schema and attribute definitions
+    ///
+    /// The existing table's description is returned, as some infrastructure
+    /// tools expect. A conflicting schema still fails with
+    /// `ResourceInUseException`.
+    pub fn idempotent_create_table(mut self, enabled: bool) -> Self {
+        self.idempotent_create_table = enabled;
+        self
+    }
+
+    /// Pre-allocate room for `tables` tables of `items_per_table` items each
+    ///
+    /// Purely a performance affordance for large loads: it avoids rehashing as
+    /// the store grows and changes nothing observable. Call it before creating
+    /// any tables, since it replaces the store.
+    pub fn with_capacity_hint(mut self, tables: usize, items_per_table: usize) -> Self {
+        self.store = Arc::new(Mutex::new(HashMap::with_capacity(tables)));
+        self.items_per_table = items_per_table;
+        self
+    }
+
+    /// Create `table_name` directly, without going through the client
+    ///
+    /// Fails with `ResourceInUseException` if the table already exists, just
+    /// like `CreateTable` does, and with `ValidationException` in strict mode
+    /// or unless `key_schema` names one or two attributes.
+    pub fn create_table(
+        &self,
+        table_name: &str,
+        key_schema: &[&str],
+    ) -> Result<(), error::CreateTableError> {
+        let key_schema: Vec<String> = key_schema.iter().map(|s| s.to_string()).collect();
+        self.create_table_with_items(table_name, key_schema, Vec::new())
+    }
+
+    /// [`create_table`](Self::create_table), with `items` already keyed for
+    /// the new table's key schema
+    fn create_table_with_items(
+        &self,
+        table_name: &str,
+        key_schema: Vec<String>,
+        items: Vec<(Vec<String>, HashMap<String, model::AttributeValue>)>,
+    ) -> Result<(), error::CreateTableError> {
+        validate_key_schema_length(key_schema.len())
+            .map_err(error::CreateTableError::ValidationException)?;
+        if self.strict {
+            return Err(error::CreateTableError::ValidationException(
+                validation_error(format!(
+                    "Cannot create table {table_name} directly: tables must be created with CreateTable in strict mode"
+                )),
+            ));
+        }
+        let creation_date_time = DateTime::from(self.clock.now());
         match self.store.lock().unwrap().entry(table_name.to_string()) {
             Entry::Vacant(v) => {
+                let mut table_items = HashMap::with_capacity(self.items_per_table);
+                table_items.extend(items);
                 v.insert(TableStore {
-                    schema: key_schema.iter().map(|s| s.to_string()).collect(),
-                    items: HashMap::new(),
+                    schema: key_schema,
+                    attribute_types: HashMap::new(),
+                    local_secondary_indexes: Vec::new(),
+                    global_secondary_indexes: Vec::new(),
+                    items: table_items,
+                    creation_date_time,
                 });
+                Ok(())
+            }
+            Entry::Occupied(_) => Err(error::CreateTableError::ResourceInUseException(
+                error::ResourceInUseException::builder()
+                    .message(Some(format!("Table {table_name} already exists")))
+                    .build(),
+            )),
+        }
+    }
+
+    /// Every item in the partition `pk` of `table_name`, ordered by sort key
+    ///
+    /// A direct read of the store for test assertions; no key condition
+    /// expression required. Returns an empty list if the table doesn't exist
+    /// or has no key schema.
+    pub fn query_partition(
+        &self,
+        table_name: &str,
+        pk: model::AttributeValue,
+    ) -> Vec<HashMap<String, model::AttributeValue>> {
+        let store = self.store.lock().unwrap();
+        let Some(table) = store.get(table_name) else {
+            return Vec::new();
+        };
+        let Some(hash_key) = table.schema.first() else {
+            return Vec::new();
+        };
+        let Some(pk) = key_part(&pk) else {
+            return Vec::new();
+        };
+        let mut items: Vec<_> = table
+            .items
+            .values()
+            .filter(|item| item.get(hash_key).and_then(key_part).as_ref() == Some(&pk))
+            .cloned()
+            .collect();
+        if let Some(range_key) = table.schema.get(1) {
+            items.sort_by(|a, b| {
+                compare_values(&a[range_key], &b[range_key]).unwrap_or(Ordering::Equal)
+            });
+        }
+        items
+    }
+
+    /// Stream every item in `table_name`, `page_size` items at a time
+    ///
+    /// The stream reads from a snapshot taken when it is created, so writes
+    /// made while iterating are not observed. A missing table yields no pages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is 0.
+    pub fn scan_stream(
+        &self,
+        table_name: &str,
+        page_size: usize,
+    ) -> impl Stream<Item = Vec<HashMap<String, model::AttributeValue>>> + use<> {
+        assert!(page_size > 0, "page_size must be positive");
+        let snapshot: Vec<_> = self
+            .store
+            .lock()
+            .unwrap()
+            .get(table_name)
+            .map(|table| table.items.values().cloned().collect())
+            .unwrap_or_default();
+        let mut items = snapshot.into_iter();
+        futures_util::stream::iter(std::iter::from_fn(move || {
+            let page: Vec<_> = items.by_ref().take(page_size).collect();
+            (!page.is_empty()).then_some(page)
+        }))
+    }
+
+    /// Insert `items` into `table_name` under a single lock, overwriting
+    ///
+    /// Meant for seeding large data sets: no conditions are evaluated. Every
+    /// item, whether a plain map or an [`Item`], must carry valid key
+    /// attributes; if one doesn't, the call fails with `ValidationException`
+    /// and nothing is inserted.
+    pub fn bulk_insert(
+        &self,
+        table_name: &str,
+        items: impl IntoIterator<Item = impl Into<Item>>,
+    ) -> Result<(), error::PutItemError> {
+        let mut store = self.store.lock().unwrap();
+        let table = store.get_mut(table_name).ok_or_else(|| {
+            error::PutItemError::ResourceNotFoundException(table_not_found(table_name))
+        })?;
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let item = item.into().into_inner();
+                Ok((table.key_from_item(&item)?, item))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error::PutItemError::ValidationException)?;
+        table.items.extend(items);
+        Ok(())
+    }
+
+    /// Create every table in `fixture` and insert its items
+    ///
+    /// Every key schema and item is checked first, and a key schema without
+    /// one or two attributes or an item missing its key fails with
+    /// `ValidationException` before anything is created. Each table is then
+    /// created with its items in place, failing like
+    /// [`create_table`](Self::create_table) does: an existing table fails with
+    /// `ResourceInUseException`, and tables earlier in the fixture are kept in
+    /// that case.
+    pub fn apply_fixture(&self, fixture: Fixture) -> Result<(), error::CreateTableError> {
+        let tables = fixture
+            .tables
+            .into_iter()
+            .map(|table| {
+                validate_key_schema_length(table.key_schema.len())?;
+                let items = table
+                    .items
+                    .into_iter()
+                    .map(|item| {
+                        let item = item.into_inner();
+                        Ok((encode_key(&table.key_schema, &HashMap::new(), &item)?, item))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((table.name, table.key_schema, items))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error::CreateTableError::ValidationException)?;
+        for (name, key_schema, items) in tables {
+            self.create_table_with_items(&name, key_schema, items)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the item at `key` with `new` if the stored item is `expected`
+    ///
+    /// `None` for `expected` means the item must not exist yet. Returns whether
+    /// the swap happened; the check and the write are atomic with respect to
+    /// every other operation on the store. `new` must carry the same key
+    /// attributes as `key`, otherwise the call fails with
+    /// `ValidationException`, as it does for an invalid `key`.
+    pub fn compare_and_swap(
+        &self,
+        table_name: &str,
+        key: HashMap<String, model::AttributeValue>,
+        expected: Option<HashMap<String, model::AttributeValue>>,
+        new: HashMap<String, model::AttributeValue>,
+    ) -> Result<bool, error::PutItemError> {
+        let mut store = self.store.lock().unwrap();
+        let table = store.get_mut(table_name).ok_or_else(|| {
+            error::PutItemError::ResourceNotFoundException(table_not_found(table_name))
+        })?;
+        let key = table
+            .key_from_item(&key)
+            .map_err(error::PutItemError::ValidationException)?;
+        let new_key = table
+            .key_from_item(&new)
+            .map_err(error::PutItemError::ValidationException)?;
+        if new_key != key {
+            return Err(error::PutItemError::ValidationException(validation_error(
+                "One or more parameter values were invalid: The new item's key does not match the key being swapped",
+            )));
+        }
+        if table.items.get(&key) != expected.as_ref() {
+            return Ok(false);
+        }
+        table.items.insert(key, new);
+        Ok(true)
+    }
+
+    /// Wait until the item at `key` exists and satisfies `predicate`
+    ///
+    /// Polls the store every few milliseconds, for tests where another task
+    /// writes in the background. Gives up with [`WaitForError::Timeout`] after
+    /// `timeout` of real time; the backend's [`Clock`] is not consulted. A
+    /// missing table counts as a missing item, but a key that doesn't fit an
+    /// existing table fails straight away with [`WaitForError::InvalidKey`].
+    pub async fn wait_for(
+        &self,
+        table_name: &str,
+        key: impl Into<Item>,
+        predicate: impl Fn(&Item) -> bool,
+        timeout: Duration,
+    ) -> Result<Item, WaitForError> {
+        let key = key.into();
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let item = match self.store.lock().unwrap().get(table_name) {
+                Some(table) => {
+                    let key = table
+                        .key_from_item(&key)
+                        .map_err(WaitForError::InvalidKey)?;
+                    table.items.get(&key).map(|item| Item::from(item.clone()))
+                }
+                None => None,
+            };
+            if let Some(item) = item
+                && predicate(&item)
+            {
+                return Ok(item);
             }
-            Entry::Occupied(_) => {
-                panic!("Table {table_name} already exists");
+            if tokio::time::Instant::now() >= deadline {
+                return Err(WaitForError::Timeout(timeout));
             }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    /// Make `op` on `table_name` fail with `error` until [`clear_failures`](Self::clear_failures)
+    ///
+    /// Other tables and other operations on this table keep working, which
+    /// simulates a single table becoming unavailable.
+    pub fn fail_table(&self, table_name: &str, op: OperationKind, error: ErrorKind) {
+        self.failures
+            .lock()
+            .unwrap()
+            .insert((table_name.to_string(), op), error);
+    }
+
+    /// Remove every failure registered with [`fail_table`](Self::fail_table)
+    pub fn clear_failures(&self) {
+        self.failures.lock().unwrap().clear();
+    }
+
+    fn injected_failure(&self, table_name: &str, op: OperationKind) -> Option<ErrorKind> {
+        self.failures
+            .lock()
+            .unwrap()
+            .get(&(table_name.to_string(), op))
+            .copied()
+    }
+
+    /// Record every API operation this backend and its clones receive from
+    /// now on
+    ///
+    /// Only requests made through the [`DynamoDb`] trait are recorded. Writes
+    /// made with helpers such as [`create_table`](Self::create_table),
+    /// [`bulk_insert`](Self::bulk_insert),
+    /// [`compare_and_swap`](Self::compare_and_swap) and
+    /// [`apply_fixture`](Self::apply_fixture) are not, so recreate that state
+    /// on the target backend before calling [`replay`](Self::replay).
+    ///
+    /// Starting a new recording stops adding to the previous one.
+    pub fn record(&self) -> RecordingHandle {
+        let handle = RecordingHandle::default();
+        *self.recording.lock().unwrap() = Some(handle.clone());
+        handle
+    }
+
+    /// Apply `operations` in order, as if they arrived through the API
+    ///
+    /// Returns what each operation returned, in order. A failing operation
+    /// doesn't stop the replay, so compare the outcomes with what the
+    /// recorded code saw to spot a replay that diverged. Tables are recreated
+    /// with their billing mode, throughput and secondary indexes, but not
+    /// their stream or encryption settings or tags.
+    pub async fn replay(&self, operations: impl IntoIterator<Item = Operation>) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        for operation in operations {
+            outcomes.push(match operation {
+                Operation::GetItem(input) => Outcome::GetItem(self.get_item(input).await),
+                Operation::PutItem(input) => Outcome::PutItem(self.put_item(input).await),
+                Operation::CreateTable(input) => {
+                    Outcome::CreateTable(Box::new(DynamoDb::create_table(self, input).await))
+                }
+                Operation::UpdateItem(input) => Outcome::UpdateItem(self.update_item(input).await),
+            });
+        }
+        outcomes
+    }
+
+    /// Copy every table and item as they are right now
+    ///
+    /// Compare two snapshots with [`StoreSnapshot::diff`] to assert on exactly
+    /// what a piece of code wrote.
+    pub fn snapshot(&self) -> StoreSnapshot {
+        let tables = self
+            .store
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(table_name, table)| {
+                let items = table
+                    .items
+                    .iter()
+                    .map(|(key, item)| (key.clone(), item.clone()))
+                    .collect();
+                (table_name.clone(), items)
+            })
+            .collect();
+        StoreSnapshot { tables }
+    }
+
+    /// Each table's name and current item count, sorted by name
+    ///
+    /// Taken under a single lock, so the counts are consistent with each other.
+    pub fn table_summaries(&self) -> Vec<(String, usize)> {
+        let mut summaries: Vec<_> = self
+            .store
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(table_name, table)| (table_name.clone(), table.items.len()))
+            .collect();
+        summaries.sort();
+        summaries
+    }
+
+    fn record_operation(&self, operation: impl FnOnce() -> Operation) {
+        if let Some(recording) = &*self.recording.lock().unwrap() {
+            recording.push(operation());
+        }
+    }
+
+    /// Store `item` unless the key is already present, the table is missing
+    /// or the item doesn't fit the table's key schema
+    ///
+    /// Used to cache items fetched from elsewhere without clobbering local
+    /// writes that happened in the meantime.
+    pub(crate) fn cache_item(
+        &self,
+        table_name: &str,
+        item: HashMap<String, model::AttributeValue>,
+    ) {
+        let mut store = self.store.lock().unwrap();
+        if let Some(table) = store.get_mut(table_name)
+            && let Ok(key) = table.key_from_item(&item)
+        {
+            table.items.entry(key).or_insert(item);
         }
     }
 
@@ -183,18 +734,209 @@ impl<'a> TableRef<'a> {
 
 struct TableStore {
     schema: Vec<String>,
+    /// Declared types of the key attributes; empty for tables created through
+    /// the [`InMemoryDynamoDb::create_table`] helper, which skips type checks
+    attribute_types: HashMap<String, model::ScalarAttributeType>,
+    local_secondary_indexes: Vec<model::LocalSecondaryIndex>,
+    global_secondary_indexes: Vec<model::GlobalSecondaryIndex>,
     items: HashMap<Vec<String>, HashMap<String, dynamodb_local_server_sdk::model::AttributeValue>>,
+    creation_date_time: DateTime,
 }
 
 impl TableStore {
+    fn description(&self, table_name: &str) -> model::TableDescription {
+        let key_schema = self
+            .schema
+            .iter()
+            .enumerate()
+            .map(|(i, name)| model::KeySchemaElement {
+                attribute_name: name.clone(),
+                key_type: if i == 0 {
+                    model::KeyType::Hash
+                } else {
+                    model::KeyType::Range
+                },
+            })
+            .collect();
+
+        // Like DynamoDB, omit the index lists entirely rather than returning
+        // empty ones when the table has no indexes of that kind
+        let local_secondary_indexes = (!self.local_secondary_indexes.is_empty()).then(|| {
+            self.local_secondary_indexes
+                .iter()
+                .map(|index| {
+                    model::LocalSecondaryIndexDescription::builder()
+                        .index_name(Some(index.index_name.clone()))
+                        .key_schema(Some(index.key_schema.clone()))
+                        .projection(Some(index.projection.clone()))
+                        .item_count(Some(self.index_item_count(&index.key_schema)))
+                        .build()
+                })
+                .collect()
+        });
+        let global_secondary_indexes = (!self.global_secondary_indexes.is_empty()).then(|| {
+            self.global_secondary_indexes
+                .iter()
+                .map(|index| {
+                    model::GlobalSecondaryIndexDescription::builder()
+                        .index_name(Some(index.index_name.clone()))
+                        .key_schema(Some(index.key_schema.clone()))
+                        .projection(Some(index.projection.clone()))
+                        .index_status(Some(model::IndexStatus::Active))
+                        .item_count(Some(self.index_item_count(&index.key_schema)))
+                        .build()
+                })
+                .collect()
+        });
+
+        model::TableDescription::builder()
+            .table_name(Some(table_name.to_string()))
+            .key_schema(Some(key_schema))
+            .table_status(Some(model::TableStatus::Active))
+            .creation_date_time(Some(self.creation_date_time))
+            .item_count(Some(self.items.len() as i64))
+            .local_secondary_indexes(local_secondary_indexes)
+            .global_secondary_indexes(global_secondary_indexes)
+            .build()
+    }
+
+    /// Number of items that have every key attribute of an index, and so
+    /// appear in it
+    fn index_item_count(&self, key_schema: &[model::KeySchemaElement]) -> i64 {
+        self.items
+            .values()
+            .filter(|item| {
+                key_schema
+                    .iter()
+                    .all(|key| item.contains_key(&key.attribute_name))
+            })
+            .count() as i64
+    }
+
+    /// Size metrics for the item collection (items sharing a partition key)
+    /// that `item` belongs to
+    ///
+    /// DynamoDB only tracks item collections for tables with local secondary
+    /// indexes, so other tables report nothing.
+    fn item_collection_metrics(
+        &self,
+        item: &HashMap<String, model::AttributeValue>,
+    ) -> Option<model::ItemCollectionMetrics> {
+        if self.local_secondary_indexes.is_empty() {
+            return None;
+        }
+        let hash_key = &self.schema[0];
+        let partition = item.get(hash_key)?;
+        let bytes = self.item_collection_size(partition);
+        let gb = (bytes as f64 / (1024.0 * 1024.0 * 1024.0)).floor();
+        Some(
+            model::ItemCollectionMetrics::builder()
+                .item_collection_key(Some(HashMap::from([(hash_key.clone(), partition.clone())])))
+                .size_estimate_range_gb(Some(vec![gb, gb + 1.0]))
+                .build(),
+        )
+    }
+
+    /// Total size of the items in the partition `partition`
+    ///
+    /// Partition keys are compared by their key encoding, so `5` and `5.0`
+    /// fall in the same collection just as they address the same items.
+    fn item_collection_size(&self, partition: &model::AttributeValue) -> usize {
+        let partition = key_part(partition);
+        self.items
+            .values()
+            .filter(|item| item.get(&self.schema[0]).and_then(key_part) == partition)
+            .map(dynamodb_item_size)
+            .sum()
+    }
+
+    /// Check the key attributes of `item` and encode them
+    ///
+    /// Every caller that addresses an item by key goes through here, so a
+    /// bad key is a `ValidationException` rather than a panic under the store
+    /// lock.
     fn key_from_item(
         &self,
-        item: &HashMap<String, dynamodb_local_server_sdk::model::AttributeValue>,
-    ) -> Vec<String> {
-        self.schema
-            .iter()
-            .map(|key| format!("{:?}", item.get(key).unwrap()))
-            .collect()
+        item: &HashMap<String, model::AttributeValue>,
+    ) -> Result<Vec<String>, error::ValidationException> {
+        encode_key(&self.schema, &self.attribute_types, item)
+    }
+}
+
+/// Validate the key attributes of `item` against `schema` and encode them in
+/// schema order
+///
+/// Rejects a missing key attribute, one of the wrong type, and an empty
+/// string or binary value; empty values are fine on non-key attributes. Keys
+/// can only be strings, numbers or binary values, so anything else is rejected
+/// even without an attribute definition to compare against.
+fn encode_key(
+    schema: &[String],
+    attribute_types: &HashMap<String, model::ScalarAttributeType>,
+    item: &HashMap<String, model::AttributeValue>,
+) -> Result<Vec<String>, error::ValidationException> {
+    schema
+        .iter()
+        .map(|key| {
+            let value = item.get(key).ok_or_else(|| {
+                validation_error(format!(
+                    "One or more parameter values were invalid: Missing the key {key} in the item"
+                ))
+            })?;
+            let expected = attribute_types.get(key);
+            let type_mismatch = |actual: &str| {
+                validation_error(format!(
+                    "One or more parameter values were invalid: Type mismatch for key {key} expected: {} actual: {actual}",
+                    expected.map_or("S, N or B", |expected| expected.as_str())
+                ))
+            };
+            let part = key_part(value).ok_or_else(|| type_mismatch(attribute_type(value)))?;
+            if let Some(expected) = expected
+                && expected.as_str() != attribute_type(value)
+            {
+                return Err(type_mismatch(attribute_type(value)));
+            }
+
+            let kind = match value {
+                model::AttributeValue::S(s) if s.is_empty() => "string",
+                model::AttributeValue::B(b) if b.as_ref().is_empty() => "binary",
+                _ => return Ok(part),
+            };
+            Err(validation_error(format!(
+                "One or more parameter values are not valid. The AttributeValue for a key attribute cannot contain an empty {kind} value. Key: {key}"
+            )))
+        })
+        .collect()
+}
+
+/// Canonical encoding of one key attribute, or `None` if the value's type
+/// can't be a key
+///
+/// Numbers are normalized, so `5`, `5.0` and `5E0` all address the same item.
+fn key_part(value: &model::AttributeValue) -> Option<String> {
+    match value {
+        model::AttributeValue::N(n) => match n.parse::<BigDecimal>() {
+            Ok(n) => Some(format!("N({})", n.normalized())),
+            Err(_) => Some(format!("{value:?}")),
+        },
+        model::AttributeValue::S(_) | model::AttributeValue::B(_) => Some(format!("{value:?}")),
+        _ => None,
+    }
+}
+
+/// DynamoDB's type descriptor for a value, as used in error messages
+fn attribute_type(value: &model::AttributeValue) -> &'static str {
+    match value {
+        model::AttributeValue::S(_) => "S",
+        model::AttributeValue::N(_) => "N",
+        model::AttributeValue::B(_) => "B",
+        model::AttributeValue::Bool(_) => "BOOL",
+        model::AttributeValue::Null(_) => "NULL",
+        model::AttributeValue::Ss(_) => "SS",
+        model::AttributeValue::Ns(_) => "NS",
+        model::AttributeValue::Bs(_) => "BS",
+        model::AttributeValue::L(_) => "L",
+        model::AttributeValue::M(_) => "M",
     }
 }
 
@@ -204,21 +946,36 @@ impl DynamoDb for InMemoryDynamoDb {
         &self,
         input: input::GetItemInput,
     ) -> Result<output::GetItemOutput, error::GetItemError> {
+        self.record_operation(|| Operation::GetItem(input.clone()));
+        if let Some(kind) = self.injected_failure(&input.table_name, OperationKind::GetItem) {
+            return Err(injected_error!(kind, input.table_name, GetItemError));
+        }
+
         let mut table = self.table(&input.table_name);
 
         let table_store = match table.get_mut() {
             Some(t) => t,
             None => {
                 return Err(error::GetItemError::ResourceNotFoundException(
-                    error::ResourceNotFoundException::builder()
-                        .message(Some(format!("Table: {} not found", input.table_name)))
-                        .build(),
+                    table_not_found(&input.table_name),
                 ));
             }
         };
 
-        let key = table_store.key_from_item(&input.key);
+        let key = table_store
+            .key_from_item(&input.key)
+            .map_err(error::GetItemError::ValidationException)?;
+        let projection = projection(
+            input.projection_expression.as_deref(),
+            input.attributes_to_get.as_deref(),
+            input.expression_attribute_names.as_ref(),
+        )
+        .map_err(|e| error::GetItemError::ValidationException(validation_error(e)))?;
         let item = table_store.items.get(&key).cloned();
+        let item = match projection {
+            Some(paths) => item.map(|item| project(item, &paths)),
+            None => item,
+        };
 
         Ok(output::GetItemOutput {
             item,
@@ -230,29 +987,39 @@ impl DynamoDb for InMemoryDynamoDb {
         &self,
         input: input::PutItemInput,
     ) -> Result<output::PutItemOutput, error::PutItemError> {
+        self.record_operation(|| Operation::PutItem(input.clone()));
+        if let Some(kind) = self.injected_failure(&input.table_name, OperationKind::PutItem) {
+            return Err(injected_error!(kind, input.table_name, PutItemError));
+        }
+
         let mut table = self.table(&input.table_name);
 
         let table_store = match table.get_mut() {
             Some(t) => t,
             None => {
                 return Err(error::PutItemError::ResourceNotFoundException(
-                    error::ResourceNotFoundException::builder()
-                        .message(Some(format!("Table: {} not found", input.table_name)))
-                        .build(),
+                    table_not_found(&input.table_name),
                 ));
             }
         };
 
-        // Check condition expression if present
+        let key = table_store
+            .key_from_item(&input.item)
+            .map_err(error::PutItemError::ValidationException)?;
+
+        // Check condition expression if present. Conditions always refer to the
+        // row currently stored under this key; the incoming item is never
+        // consulted, so `attribute_exists(x)` is false for a new attribute `x`.
         if let Some(condition_expr) = &input.condition_expression {
-            let key = table_store.key_from_item(&input.item);
             let existing_item = table_store.items.get(&key);
 
             let condition_met = evaluate_condition_expression(
                 condition_expr,
                 existing_item,
+                input.expression_attribute_names.as_ref(),
                 input.expression_attribute_values.as_ref(),
-            );
+            )
+            .map_err(|e| error::PutItemError::ValidationException(validation_error(e)))?;
 
             if !condition_met {
                 return Err(error::PutItemError::ConditionalCheckFailedException(
@@ -263,13 +1030,32 @@ impl DynamoDb for InMemoryDynamoDb {
             }
         }
 
-        let key = table_store.key_from_item(&input.item);
-        table_store.items.insert(key, input.item);
+        if !matches!(
+            input.return_values,
+            None | Some(model::ReturnValue::None) | Some(model::ReturnValue::AllOld)
+        ) {
+            return Err(error::PutItemError::ValidationException(validation_error(
+                "ReturnValues can only be ALL_OLD or NONE",
+            )));
+        }
+
+        let old_item = table_store.items.insert(key.clone(), input.item);
+        let item_collection_metrics = match input.return_item_collection_metrics {
+            Some(model::ReturnItemCollectionMetrics::Size) => {
+                table_store.item_collection_metrics(&table_store.items[&key])
+            }
+            _ => None,
+        };
 
         Ok(output::PutItemOutput {
-            attributes: None,
+            attributes: returned_attributes(
+                input.return_values.as_ref(),
+                old_item.as_ref(),
+                None,
+                &[],
+            ),
             consumed_capacity: None,
-            item_collection_metrics: None,
+            item_collection_metrics,
         })
     }
 
@@ -277,20 +1063,52 @@ impl DynamoDb for InMemoryDynamoDb {
         &self,
         input: input::CreateTableInput,
     ) -> Result<output::CreateTableOutput, error::CreateTableError> {
+        self.record_operation(|| Operation::CreateTable(input.clone()));
+        validate_key_schema_length(input.key_schema.len())
+            .map_err(error::CreateTableError::ValidationException)?;
+        validate_billing_mode(&input)
+            .map_err(|e| error::CreateTableError::ValidationException(validation_error(e)))?;
+
         let key_schema: Vec<String> = input
             .key_schema
             .iter()
             .map(|k| k.attribute_name.clone())
             .collect();
 
+        let attribute_types: HashMap<_, _> = input
+            .attribute_definitions
+            .iter()
+            .map(|d| (d.attribute_name.clone(), d.attribute_type.clone()))
+            .collect();
+
+        let creation_date_time = DateTime::from(self.clock.now());
         match self.store.lock().unwrap().entry(input.table_name.clone()) {
             Entry::Vacant(v) => {
-                v.insert(TableStore {
+                let table = v.insert(TableStore {
                     schema: key_schema,
-                    items: HashMap::new(),
+                    attribute_types,
+                    local_secondary_indexes: input
+                        .local_secondary_indexes
+                        .clone()
+                        .unwrap_or_default(),
+                    global_secondary_indexes: input
+                        .global_secondary_indexes
+                        .clone()
+                        .unwrap_or_default(),
+                    items: HashMap::with_capacity(self.items_per_table),
+                    creation_date_time,
                 });
                 Ok(output::CreateTableOutput {
-                    table_description: None,
+                    table_description: Some(table.description(&input.table_name)),
+                })
+            }
+            Entry::Occupied(o)
+                if self.idempotent_create_table
+                    && o.get().schema == key_schema
+                    && o.get().attribute_types == attribute_types =>
+            {
+                Ok(output::CreateTableOutput {
+                    table_description: Some(o.get().description(&input.table_name)),
                 })
             }
             Entry::Occupied(_) => Err(error::CreateTableError::ResourceInUseException(
@@ -305,68 +1123,145 @@ impl DynamoDb for InMemoryDynamoDb {
         &self,
         input: input::UpdateItemInput,
     ) -> Result<output::UpdateItemOutput, error::UpdateItemError> {
+        self.record_operation(|| Operation::UpdateItem(input.clone()));
+        if let Some(kind) = self.injected_failure(&input.table_name, OperationKind::UpdateItem) {
+            return Err(injected_error!(kind, input.table_name, UpdateItemError));
+        }
+
         let mut table = self.table(&input.table_name);
 
         let table_store = match table.get_mut() {
             Some(t) => t,
             None => {
                 return Err(error::UpdateItemError::ResourceNotFoundException(
-                    error::ResourceNotFoundException::builder()
-                        .message(Some(format!("Table: {} not found", input.table_name)))
-                        .build(),
+                    table_not_found(&input.table_name),
                 ));
             }
         };
 
-        let key = table_store.key_from_item(&input.key);
-        let item = table_store
-            .items
-            .entry(key)
-            .or_insert_with(|| input.key.clone());
-
-        // Handle update expression (SET operations only)
-        if let Some(update_expr) = &input.update_expression {
-            if let Some(attr_values) = &input.expression_attribute_values {
-                // Parse simple SET expressions like "SET #name = :val" or "SET attr = :val"
-                for set_clause in update_expr.split("SET").skip(1) {
-                    for assignment in set_clause.split(',') {
-                        let parts: Vec<&str> = assignment.split('=').map(|s| s.trim()).collect();
-                        if parts.len() == 2 {
-                            let attr_name = if parts[0].starts_with('#') {
-                                input
-                                    .expression_attribute_names
-                                    .as_ref()
-                                    .and_then(|names| names.get(parts[0]))
-                                    .map(|s| s.as_str())
-                                    .unwrap_or(parts[0])
-                            } else {
-                                parts[0]
-                            };
-
-                            if let Some(value) = attr_values.get(parts[1]) {
-                                item.insert(attr_name.to_string(), value.clone());
-                            }
-                        }
-                    }
-                }
+        let key = table_store
+            .key_from_item(&input.key)
+            .map_err(error::UpdateItemError::ValidationException)?;
+        let existing_item = table_store.items.get(&key);
+
+        if let Some(condition_expr) = &input.condition_expression {
+            let condition_met = evaluate_condition_expression(
+                condition_expr,
+                existing_item,
+                input.expression_attribute_names.as_ref(),
+                input.expression_attribute_values.as_ref(),
+            )
+            .map_err(|e| error::UpdateItemError::ValidationException(validation_error(e)))?;
+
+            if !condition_met {
+                return Err(error::UpdateItemError::ConditionalCheckFailedException(
+                    error::ConditionalCheckFailedException::builder()
+                        .message(Some("The conditional request failed".to_string()))
+                        .build(),
+                ));
+            }
+        }
+
+        let actions = match (&input.update_expression, &input.attribute_updates) {
+            (Some(_), Some(_)) => Err(
+                "Can not use both expression and non-expression parameters in the same request: Non-expression parameters: {AttributeUpdates} Expression parameters: {UpdateExpression}"
+                    .to_string(),
+            ),
+            (Some(expr), None) => parse_update_expression(
+                expr,
+                input.expression_attribute_names.as_ref(),
+                input.expression_attribute_values.as_ref(),
+            )
+            .map(Some),
+            (None, Some(updates)) => attribute_updates_to_actions(updates).map(Some),
+            (None, None) => Ok(None),
+        }
+        .map_err(|e| error::UpdateItemError::ValidationException(validation_error(e)))?;
+
+        if let Some(actions) = &actions
+            && let Some(key_attr) = actions
+                .paths()
+                .find(|path| table_store.schema.iter().any(|k| k == path))
+        {
+            return Err(error::UpdateItemError::ValidationException(
+                validation_error(format!(
+                    "One or more parameter values were invalid: Cannot update attribute {key_attr}. This attribute is part of the key"
+                )),
+            ));
+        }
+
+        // A missing item is seeded from the key, but only actions that write
+        // attributes (SET/ADD) actually bring it into existence
+        let old_item = existing_item.cloned();
+        let mut item = old_item.clone().unwrap_or_else(|| input.key.clone());
+        if let Some(actions) = &actions {
+            if existing_item.is_none() && !actions.creates_item() {
+                return Ok(output::UpdateItemOutput {
+                    attributes: None,
+                    consumed_capacity: None,
+                    item_collection_metrics: None,
+                });
             }
+            actions
+                .apply(&mut item)
+                .map_err(|e| error::UpdateItemError::ValidationException(validation_error(e)))?;
         }
+        let updated: Vec<&str> = actions.iter().flat_map(|a| a.paths()).collect();
+        let attributes = returned_attributes(
+            input.return_values.as_ref(),
+            old_item.as_ref(),
+            Some(&item),
+            &updated,
+        );
+        table_store.items.insert(key, item);
 
         Ok(output::UpdateItemOutput {
-            attributes: None,
+            attributes,
             consumed_capacity: None,
             item_collection_metrics: None,
         })
     }
+
+    /// Every operation in the model; extend this as operations are added
+    fn supported_operations(&self) -> &'static [&'static str] {
+        &["GetItem", "PutItem", "CreateTable", "UpdateItem"]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{assert_conditional_check_failed, assert_validation_error};
     use aws_sdk_dynamodb::types::AttributeValue;
     use rstest::rstest;
     use std::collections::HashMap;
 
+    /// Update `row` in `test-table` under `condition` and check whether it
+    /// applied, with `values` bound as expression attribute values
+    async fn assert_condition(
+        client: &Client,
+        condition: &str,
+        values: &[(&str, AttributeValue)],
+        expected: bool,
+    ) {
+        let mut update = client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("row".to_string()))
+            .update_expression("SET touched = :touched")
+            .condition_expression(condition)
+            .expression_attribute_values(":touched", AttributeValue::Bool(true));
+        for (placeholder, value) in values {
+            update = update.expression_attribute_values(*placeholder, value.clone());
+        }
+        let result = update.send().await;
+        if expected {
+            assert!(result.is_ok(), "{condition} with {values:?}: {result:?}");
+        } else {
+            assert_conditional_check_failed(result);
+        }
+    }
+
     #[rstest]
     #[case::in_memory(TestBackendType::InMemory)]
     #[case::dynamodb_local(TestBackendType::DynamoDbLocal)]
@@ -407,10 +1302,54 @@ mod tests {
         assert!(response.item.is_some());
     }
 
+    #[rstest]
+    #[case::in_memory(TestBackendType::InMemory)]
+    #[case::dynamodb_local(TestBackendType::DynamoDbLocal)]
+    #[tokio::test]
+    async fn test_put_item_replaces_whole_item(#[case] backend_type: TestBackendType) {
+        let (client, backend) = create_test_client(backend_type).await;
+        backend.create_table("test-table", &["id"]);
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("x".to_string()))
+            .item("a", AttributeValue::S("1".to_string()))
+            .item("b", AttributeValue::S("2".to_string()))
+            .send()
+            .await
+            .unwrap();
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("x".to_string()))
+            .item("a", AttributeValue::S("3".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("x".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(
+            item,
+            HashMap::from([
+                ("id".to_string(), AttributeValue::S("x".to_string())),
+                ("a".to_string(), AttributeValue::S("3".to_string())),
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn test_get_nonexistent_item() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
         let mut key = HashMap::new();
         key.insert(
@@ -433,7 +1372,7 @@ mod tests {
     #[tokio::test]
     async fn test_conditional_put_attribute_not_exists_success() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
         let mut item = HashMap::new();
         item.insert("id".to_string(), AttributeValue::S("new-id".to_string()));
@@ -456,7 +1395,7 @@ mod tests {
     #[tokio::test]
     async fn test_conditional_put_attribute_not_exists_failure() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
         // First, put an item
         let mut item = HashMap::new();
@@ -499,7 +1438,7 @@ mod tests {
     #[tokio::test]
     async fn test_conditional_put_and_expression() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id", "sk"]);
+        store.create_table("test-table", &["id", "sk"]).unwrap();
 
         let mut item = HashMap::new();
         item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
@@ -523,7 +1462,7 @@ mod tests {
     #[tokio::test]
     async fn test_conditional_put_and_expression_partial_failure() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
         // First, put an item with 'id'
         let mut existing_item = HashMap::new();
@@ -572,413 +1511,561 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_multiple_clients_same_store() {
-        let (client1, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("shared-table", &["id"]);
-
-        // Create second client by cloning the first
-        let client2 = client1.clone();
+    async fn test_conditional_put_equality_without_spaces() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
-        // Put item with client1
         let mut item = HashMap::new();
-        item.insert("id".to_string(), AttributeValue::S("shared-id".to_string()));
-
-        client1
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert("version".to_string(), AttributeValue::N("1".to_string()));
+        client
             .put_item()
-            .table_name("shared-table")
+            .table_name("test-table")
             .set_item(Some(item.clone()))
             .send()
             .await
             .unwrap();
 
-        // Get item with client2
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("shared-id".to_string()));
-
-        let get_result = client2
-            .get_item()
-            .table_name("shared-table")
-            .set_key(Some(key))
-            .send()
-            .await;
-
-        assert!(get_result.is_ok());
-        let response = get_result.unwrap();
-        assert!(response.item.is_some());
-    }
-
-    #[tokio::test]
-    #[ignore]
-    async fn test_transact_write_items() {
-        let (client, _store) = create_in_memory_dynamodb_client().await;
-
-        let mut item1 = HashMap::new();
-        item1.insert("id".to_string(), AttributeValue::S("txn-1".to_string()));
-        item1.insert("data".to_string(), AttributeValue::S("value1".to_string()));
-
-        let mut item2 = HashMap::new();
-        item2.insert("id".to_string(), AttributeValue::S("txn-2".to_string()));
-        item2.insert("data".to_string(), AttributeValue::S("value2".to_string()));
-
-        let txn_result = client
-            .transact_write_items()
-            .transact_items(
-                aws_sdk_dynamodb::types::TransactWriteItem::builder()
-                    .put(
-                        aws_sdk_dynamodb::types::Put::builder()
-                            .table_name("test-table")
-                            .set_item(Some(item1.clone()))
-                            .build()
-                            .unwrap(),
-                    )
-                    .build(),
-            )
-            .transact_items(
-                aws_sdk_dynamodb::types::TransactWriteItem::builder()
-                    .put(
-                        aws_sdk_dynamodb::types::Put::builder()
-                            .table_name("test-table")
-                            .set_item(Some(item2.clone()))
-                            .build()
-                            .unwrap(),
-                    )
-                    .build(),
-            )
+        let put_result = client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .condition_expression("version=:v")
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
             .send()
             .await;
+        assert!(put_result.is_ok());
 
-        assert!(txn_result.is_ok());
-
-        // Verify items were written
-        let mut key1 = HashMap::new();
-        key1.insert("id".to_string(), AttributeValue::S("txn-1".to_string()));
-        key1.insert("data".to_string(), AttributeValue::S("value1".to_string()));
-
-        let get_result = client
-            .get_item()
+        let put_result = client
+            .put_item()
             .table_name("test-table")
-            .set_key(Some(key1))
+            .set_item(Some(item))
+            .condition_expression("version=:v")
+            .expression_attribute_values(":v", AttributeValue::N("2".to_string()))
             .send()
             .await;
-
-        assert!(get_result.is_ok());
-        assert!(get_result.unwrap().item.is_some());
+        match put_result.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::put_item::PutItemError::ConditionalCheckFailedException(_) => {}
+            other => panic!("Expected ConditionalCheckFailedException, got: {:?}", other),
+        }
     }
 
     #[tokio::test]
-    async fn test_condition_expression_basic() {
-        let (dynamodb_client, dynamodb_store) = create_in_memory_dynamodb_client().await;
-
-        // Initialize the DynamoDB table for Slate
-        dynamodb_store.create_table("test-table", &["shard_id", "sequence_number"]);
+    async fn test_conditional_put_placeholders_containing_equals() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
-        // Create a test item
         let mut item = HashMap::new();
-        item.insert(
-            "shard_id".to_string(),
-            AttributeValue::S("test-shard".to_string()),
-        );
-        item.insert(
-            "sequence_number".to_string(),
-            AttributeValue::S("1".to_string()),
-        );
-        item.insert(
-            "batch_id".to_string(),
-            AttributeValue::S("test-batch".to_string()),
-        );
-
-        // First put should succeed
-        let result1 = dynamodb_client
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert("a = b".to_string(), AttributeValue::S("x = y".to_string()));
+        client
             .put_item()
             .table_name("test-table")
             .set_item(Some(item.clone()))
-            .condition_expression("attribute_not_exists(sequence_number)")
             .send()
-            .await;
-
-        assert!(result1.is_ok(), "First put should succeed");
+            .await
+            .unwrap();
 
-        // Second put with same key should fail
-        let result2 = dynamodb_client
+        let put_result = client
             .put_item()
             .table_name("test-table")
-            .set_item(Some(item.clone()))
-            .condition_expression("attribute_not_exists(sequence_number)")
+            .set_item(Some(item))
+            .condition_expression("#attr = :val")
+            .expression_attribute_names("#attr", "a = b")
+            .expression_attribute_values(":val", AttributeValue::S("x = y".to_string()))
             .send()
             .await;
-
-        assert!(result2.is_err(), "Second put should fail due to condition");
-
-        // Verify it's the right type of error
-        match result2.unwrap_err().into_service_error() {
-            aws_sdk_dynamodb::operation::put_item::PutItemError::ConditionalCheckFailedException(_) => {
-                // Expected error type
-            }
-            other => panic!("Expected ConditionalCheckFailedException, got: {:?}", other),
-        }
+        assert!(put_result.is_ok());
     }
 
     #[tokio::test]
-    async fn test_get_item_table_not_found() {
-        let (client, _store) = create_in_memory_dynamodb_client().await;
-        // Note: we don't insert the table, so it should not exist
+    async fn test_conditional_put_undefined_placeholder() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
 
-        let get_result = client
-            .get_item()
-            .table_name("nonexistent-table")
-            .set_key(Some(key))
+        let put_result = client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item))
+            .condition_expression("#missing = :val")
+            .expression_attribute_values(":val", AttributeValue::S("x".to_string()))
             .send()
             .await;
 
-        assert!(get_result.is_err());
-        match get_result.unwrap_err().into_service_error() {
-            aws_sdk_dynamodb::operation::get_item::GetItemError::ResourceNotFoundException(e) => {
-                assert!(
-                    e.message()
-                        .unwrap()
-                        .contains("Table: nonexistent-table not found")
-                );
-            }
-            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
-        }
+        assert_validation_error(put_result);
     }
 
     #[tokio::test]
-    async fn test_put_item_table_not_found() {
-        let (client, _store) = create_in_memory_dynamodb_client().await;
-        // Note: we don't insert the table, so it should not exist
+    async fn test_conditional_put_evaluates_existing_item() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
         let mut item = HashMap::new();
-        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
-        item.insert(
-            "name".to_string(),
-            AttributeValue::S("test-name".to_string()),
-        );
+        item.insert("id".to_string(), AttributeValue::S("row".to_string()));
+        client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await
+            .unwrap();
 
+        // The incoming item has `x`, but the stored row does not
+        item.insert("x".to_string(), AttributeValue::N("1".to_string()));
         let put_result = client
             .put_item()
-            .table_name("nonexistent-table")
+            .table_name("test-table")
             .set_item(Some(item))
+            .condition_expression("attribute_exists(x)")
             .send()
             .await;
 
-        assert!(put_result.is_err());
-        match put_result.unwrap_err().into_service_error() {
-            aws_sdk_dynamodb::operation::put_item::PutItemError::ResourceNotFoundException(e) => {
-                assert!(
-                    e.message()
-                        .unwrap()
-                        .contains("Table: nonexistent-table not found")
-                );
-            }
-            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
-        }
+        assert_conditional_check_failed(put_result);
     }
 
     #[tokio::test]
-    async fn test_update_item_table_not_found() {
-        let (client, _store) = create_in_memory_dynamodb_client().await;
-        // Note: we don't insert the table, so it should not exist
-
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+    async fn test_conditional_update_comparison_operators() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
-        let update_result = client
-            .update_item()
-            .table_name("nonexistent-table")
-            .set_key(Some(key))
-            .update_expression("SET #name = :val")
-            .expression_attribute_names("#name", "name")
-            .expression_attribute_values(":val", AttributeValue::S("new-value".to_string()))
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("version", AttributeValue::N("3".to_string()))
+            .item("label", AttributeValue::S("m".to_string()))
             .send()
-            .await;
+            .await
+            .unwrap();
 
-        assert!(update_result.is_err());
-        match update_result.unwrap_err().into_service_error() {
-            aws_sdk_dynamodb::operation::update_item::UpdateItemError::ResourceNotFoundException(e) => {
-                assert!(e.message().unwrap().contains("Table: nonexistent-table not found"));
-            }
-            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        let values = [
+            (":one", AttributeValue::N("1".to_string())),
+            (":three", AttributeValue::N("3.0".to_string())),
+            (":ten", AttributeValue::N("10".to_string())),
+            (":a", AttributeValue::S("a".to_string())),
+            (":c", AttributeValue::S("c".to_string())),
+        ];
+        for (condition, expected) in [
+            ("version = :three", true),
+            ("version = :one", false),
+            ("version < :ten", true),
+            ("version >= :ten", false),
+            ("version <= :three", true),
+            ("version BETWEEN :one AND :ten", true),
+            ("label BETWEEN :a AND :c", false),
+            ("version IN (:one, :three)", true),
+            ("NOT (version > :one)", false),
+            ("(version > :one OR absent = :one) AND label <> :a", true),
+            ("absent < :ten", false),
+        ] {
+            assert_condition(&client, condition, &values, expected).await;
         }
     }
 
     #[tokio::test]
-    async fn test_update_item_creates_if_not_exists() {
+    async fn test_update_item_only_while_ttl_is_in_the_future() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
-
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("new-id".to_string()));
+        store.create_table("test-table", &["id"]).unwrap();
 
         client
-            .update_item()
+            .put_item()
             .table_name("test-table")
-            .set_key(Some(key.clone()))
-            .update_expression("SET #name = :val")
-            .expression_attribute_names("#name", "name")
-            .expression_attribute_values(":val", AttributeValue::S("test-name".to_string()))
+            .item("id", AttributeValue::S("lease".to_string()))
+            .item("ttl", AttributeValue::N("1000".to_string()))
+            .item("version", AttributeValue::N("1".to_string()))
             .send()
             .await
             .unwrap();
 
-        let get_result = client
+        let renew = |now: &str| {
+            client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("lease".to_string()))
+                .update_expression("SET version = version + :one")
+                .condition_expression("#ttl > :now")
+                .expression_attribute_names("#ttl", "ttl")
+                .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+                .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+                .send()
+        };
+
+        renew("999").await.unwrap();
+        assert_conditional_check_failed(renew("1000").await);
+        assert_conditional_check_failed(renew("2000").await);
+
+        let item = client
             .get_item()
             .table_name("test-table")
-            .set_key(Some(key))
+            .key("id", AttributeValue::S("lease".to_string()))
             .send()
             .await
+            .unwrap()
+            .item
             .unwrap();
-
-        assert!(get_result.item.is_some());
-        let item = get_result.item.unwrap();
-        assert_eq!(item.get("name").unwrap().as_s().unwrap(), "test-name");
+        assert_eq!(item["version"], AttributeValue::N("2".to_string()));
     }
 
     #[tokio::test]
-    async fn test_update_item_modifies_existing() {
+    async fn test_reserved_words_must_be_aliased() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
-        let mut item = HashMap::new();
-        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
-        item.insert(
-            "name".to_string(),
-            AttributeValue::S("old-name".to_string()),
+        let update = |expression: &str| {
+            client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("row".to_string()))
+                .update_expression(expression)
+                .expression_attribute_values(":v", AttributeValue::N("3".to_string()))
+        };
+
+        let err = update("SET size = :v").send().await.unwrap_err();
+        assert_eq!(
+            err.into_service_error().meta().message(),
+            Some(
+                "Invalid UpdateExpression: Attribute name is a reserved keyword; reserved keyword: size"
+            )
         );
-        item.insert("count".to_string(), AttributeValue::N("5".to_string()));
+        update("SET #s = :v")
+            .expression_attribute_names("#s", "size")
+            .send()
+            .await
+            .unwrap();
 
-        client
-            .put_item()
-            .table_name("test-table")
-            .set_item(Some(item.clone()))
+        assert_validation_error(
+            update("SET #s = :v")
+                .expression_attribute_names("#s", "size")
+                .condition_expression("Size = :v")
+                .send()
+                .await,
+        );
+        update("SET #s = :v")
+            .expression_attribute_names("#s", "size")
+            .condition_expression("#s = :v")
             .send()
             .await
             .unwrap();
+    }
 
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+    #[tokio::test]
+    async fn test_conditional_and_binds_tighter_than_or() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
 
         client
-            .update_item()
+            .put_item()
             .table_name("test-table")
-            .set_key(Some(key.clone()))
-            .update_expression("SET #name = :val")
-            .expression_attribute_names("#name", "name")
-            .expression_attribute_values(":val", AttributeValue::S("new-name".to_string()))
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("a", AttributeValue::N("1".to_string()))
+            .item("b", AttributeValue::N("2".to_string()))
             .send()
             .await
             .unwrap();
 
-        let get_result = client
-            .get_item()
-            .table_name("test-table")
-            .set_key(Some(key))
-            .send()
+        let values = [
+            (":one", AttributeValue::N("1".to_string())),
+            (":two", AttributeValue::N("2".to_string())),
+        ];
+        for (condition, expected) in [
+            // Evaluated left to right these would be false
+            ("a = :one OR attribute_not_exists(a) AND b = :one", true),
+            ("a = :one OR b = :one AND a = :two", true),
+            ("a = :two OR attribute_exists(a) AND b = :two", true),
+            ("a = :two AND b = :two OR a = :one", true),
+            // Parentheses and NOT still group as written
+            ("a = :two AND (b = :two OR a = :one)", false),
+            ("(a = :one OR b = :one) AND a = :two", false),
+            ("NOT a = :two AND b = :one", false),
+            ("NOT (a = :two AND b = :one)", true),
+        ] {
+            assert_condition(&client, condition, &values, expected).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_comparison_across_types() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("price", AttributeValue::N("10".to_string()))
+            .send()
             .await
             .unwrap();
 
-        let updated_item = get_result.item.unwrap();
-        assert_eq!(
-            updated_item.get("name").unwrap().as_s().unwrap(),
-            "new-name"
-        );
-        assert_eq!(updated_item.get("count").unwrap().as_n().unwrap(), "5");
+        for (condition, expected) in [
+            ("price < :s", false),
+            ("price >= :s", false),
+            ("price BETWEEN :s AND :s", false),
+            ("price = :s", false),
+            ("price <> :s", true),
+        ] {
+            let values = [(":s", AttributeValue::S("10".to_string()))];
+            assert_condition(&client, condition, &values, expected).await;
+        }
     }
 
     #[tokio::test]
-    async fn test_update_item_multiple_attributes() {
+    async fn test_conditional_number_membership_is_normalized() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
-
-        let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        store.create_table("test-table", &["id"]).unwrap();
 
         client
-            .update_item()
+            .put_item()
             .table_name("test-table")
-            .set_key(Some(key.clone()))
-            .update_expression("SET #name = :name, #status = :status")
-            .expression_attribute_names("#name", "name")
-            .expression_attribute_names("#status", "status")
-            .expression_attribute_values(":name", AttributeValue::S("test-name".to_string()))
-            .expression_attribute_values(":status", AttributeValue::S("active".to_string()))
+            .item("id", AttributeValue::S("row".to_string()))
+            .item(
+                "ages",
+                AttributeValue::Ns(vec!["18".to_string(), "21.5".to_string()]),
+            )
+            .item(
+                "scores",
+                AttributeValue::L(vec![AttributeValue::N("7".to_string())]),
+            )
+            .item("version", AttributeValue::N("10".to_string()))
             .send()
             .await
             .unwrap();
 
-        let get_result = client
-            .get_item()
+        for (condition, value, expected) in [
+            ("contains(ages, :n)", "18.0", true),
+            ("contains(ages, :n)", "2.15E1", true),
+            ("contains(ages, :n)", "19", false),
+            ("contains(scores, :n)", "7.00", true),
+            ("version IN (:n, :other)", "1E1", true),
+            ("version IN (:n, :other)", "100", false),
+        ] {
+            let values = [
+                (":n", AttributeValue::N(value.to_string())),
+                (":other", AttributeValue::N("-1".to_string())),
+            ];
+            assert_condition(&client, condition, &values, expected).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_size_and_nested_paths() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        client
+            .put_item()
             .table_name("test-table")
-            .set_key(Some(key))
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("description", AttributeValue::S("héllo".to_string()))
+            .item(
+                "meta",
+                AttributeValue::M(HashMap::from([(
+                    "verified".to_string(),
+                    AttributeValue::Bool(true),
+                )])),
+            )
+            .item(
+                "tags",
+                AttributeValue::L(vec![
+                    AttributeValue::S("a".to_string()),
+                    AttributeValue::M(HashMap::from([(
+                        "label".to_string(),
+                        AttributeValue::S("b".to_string()),
+                    )])),
+                ]),
+            )
+            .item("version", AttributeValue::N("1".to_string()))
             .send()
             .await
             .unwrap();
 
-        let item = get_result.item.unwrap();
-        assert_eq!(item.get("name").unwrap().as_s().unwrap(), "test-name");
-        assert_eq!(item.get("status").unwrap().as_s().unwrap(), "active");
+        let values = [
+            (":zero", AttributeValue::N("0".to_string())),
+            (":one", AttributeValue::N("1".to_string())),
+            (":two", AttributeValue::N("2".to_string())),
+            (":five", AttributeValue::N("5".to_string())),
+            (":min", AttributeValue::N("3".to_string())),
+            (":a", AttributeValue::S("a".to_string())),
+            (":b", AttributeValue::S("b".to_string())),
+        ];
+        for (condition, expected) in [
+            ("size(description) > :min", true),
+            ("size(description) = :five", true),
+            ("size(tags) = :two", true),
+            ("size(meta) < :min", true),
+            ("size(version) > :zero", false),
+            ("size(absent) > :zero", false),
+            ("attribute_exists(meta.verified)", true),
+            ("attribute_not_exists(meta.verified)", false),
+            ("attribute_exists(meta.absent)", false),
+            ("attribute_exists(description.verified)", false),
+            ("tags[0] = :a", true),
+            ("tags[1].label = :b", true),
+            ("attribute_exists(tags[2])", false),
+            ("size(tags[1].label) = :one", true),
+        ] {
+            assert_condition(&client, condition, &values, expected).await;
+        }
+
+        // Each segment of a nested path is checked against reserved words
+        let err = client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .condition_expression("attribute_exists(meta.name)")
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.into_service_error().meta().message(),
+            Some(
+                "Invalid ConditionExpression: Attribute name is a reserved keyword; reserved keyword: name"
+            )
+        );
     }
 
     #[tokio::test]
-    #[ignore = "query not supported yet"]
-    async fn test_query_table_not_found() {
-        let (client, _store) = create_in_memory_dynamodb_client().await;
-        // Note: we don't insert the table, so it should not exist
+    async fn test_binary_set_updates_and_conditions() {
+        use aws_sdk_dynamodb::primitives::Blob;
 
-        let query_result = client
-            .query()
-            .table_name("nonexistent-table")
-            .key_condition_expression("pk = :pk")
-            .expression_attribute_values(":pk", AttributeValue::S("test-key".to_string()))
-            .send()
-            .await;
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        let blobs = |members: &[&[u8]]| {
+            AttributeValue::Bs(members.iter().map(|m| Blob::new(m.to_vec())).collect())
+        };
+        let update = |expression: &str, value: AttributeValue| {
+            client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("row".to_string()))
+                .update_expression(expression)
+                .expression_attribute_values(":v", value)
+                .return_values(aws_sdk_dynamodb::types::ReturnValue::AllNew)
+                .send()
+        };
 
-        assert!(query_result.is_err());
-        match query_result.unwrap_err().into_service_error() {
-            aws_sdk_dynamodb::operation::query::QueryError::ResourceNotFoundException(e) => {
-                assert!(
-                    e.message()
-                        .unwrap()
-                        .contains("Table: nonexistent-table not found")
-                );
-            }
-            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        update("ADD tags :v", blobs(&[b"a", b"b"])).await.unwrap();
+        let output = update("ADD tags :v", blobs(&[b"b", b"c"])).await.unwrap();
+        assert_eq!(
+            output.attributes.unwrap()["tags"],
+            blobs(&[b"a", b"b", b"c"])
+        );
+        let output = update("DELETE tags :v", blobs(&[b"a", b"missing"]))
+            .await
+            .unwrap();
+        assert_eq!(output.attributes.unwrap()["tags"], blobs(&[b"b", b"c"]));
+
+        for (condition, value, expected) in [
+            (
+                "contains(tags, :v)",
+                AttributeValue::B(Blob::new(b"c".to_vec())),
+                true,
+            ),
+            (
+                "contains(tags, :v)",
+                AttributeValue::B(Blob::new(b"a".to_vec())),
+                false,
+            ),
+            ("tags = :v", blobs(&[b"c", b"b"]), true),
+            ("tags = :v", blobs(&[b"b"]), false),
+        ] {
+            assert_condition(&client, condition, &[(":v", value)], expected).await;
         }
     }
 
     #[tokio::test]
-    async fn test_existing_functionality_still_works() {
-        // This test ensures that existing functionality still works after our changes
+    async fn test_conditional_put_binary_values() {
+        use aws_sdk_dynamodb::primitives::Blob;
+
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("test-table", &["id"]);
+        store.create_table("test-table", &["id"]).unwrap();
 
-        // Put an item
         let mut item = HashMap::new();
         item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
         item.insert(
-            "name".to_string(),
-            AttributeValue::S("test-name".to_string()),
+            "payload".to_string(),
+            AttributeValue::B(Blob::new(vec![0xde, 0xad, 0xbe, 0xef])),
         );
-
-        let put_result = client
+        client
             .put_item()
             .table_name("test-table")
             .set_item(Some(item.clone()))
             .send()
-            .await;
+            .await
+            .unwrap();
 
-        assert!(put_result.is_ok());
+        let put_with_condition = |condition: &str, value: Vec<u8>| {
+            client
+                .put_item()
+                .table_name("test-table")
+                .set_item(Some(item.clone()))
+                .condition_expression(condition)
+                .expression_attribute_values(":b", AttributeValue::B(Blob::new(value)))
+                .send()
+        };
 
-        // Get the item back
+        assert!(
+            put_with_condition("payload = :b", vec![0xde, 0xad, 0xbe, 0xef])
+                .await
+                .is_ok()
+        );
+        assert!(
+            put_with_condition("begins_with(payload, :b)", vec![0xde, 0xad])
+                .await
+                .is_ok()
+        );
+        assert!(
+            put_with_condition("contains(payload, :b)", vec![0xad, 0xbe])
+                .await
+                .is_ok()
+        );
+
+        for (condition, value) in [
+            ("payload = :b", vec![0xde, 0xad]),
+            ("begins_with(payload, :b)", vec![0xbe, 0xef]),
+            ("contains(payload, :b)", vec![0xef, 0xde]),
+        ] {
+            match put_with_condition(condition, value)
+                .await
+                .unwrap_err()
+                .into_service_error()
+            {
+                aws_sdk_dynamodb::operation::put_item::PutItemError::ConditionalCheckFailedException(_) => {}
+                other => panic!("Expected ConditionalCheckFailedException for {condition}, got: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiple_clients_same_store() {
+        let (client1, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("shared-table", &["id"]).unwrap();
+
+        // Create second client by cloning the first
+        let client2 = client1.clone();
+
+        // Put item with client1
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("shared-id".to_string()));
+
+        client1
+            .put_item()
+            .table_name("shared-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await
+            .unwrap();
+
+        // Get item with client2
         let mut key = HashMap::new();
-        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        key.insert("id".to_string(), AttributeValue::S("shared-id".to_string()));
 
-        let get_result = client
+        let get_result = client2
             .get_item()
-            .table_name("test-table")
+            .table_name("shared-table")
             .set_key(Some(key))
             .send()
             .await;
@@ -986,56 +2073,2139 @@ mod tests {
         assert!(get_result.is_ok());
         let response = get_result.unwrap();
         assert!(response.item.is_some());
-        assert_eq!(
-            response.item.unwrap().get("name").unwrap().as_s().unwrap(),
-            "test-name"
-        );
     }
 
     #[tokio::test]
-    async fn test_create_table() {
+    #[ignore]
+    async fn test_transact_write_items() {
         let (client, _store) = create_in_memory_dynamodb_client().await;
 
-        let result = client
-            .create_table()
-            .table_name("new-table")
-            .key_schema(
-                aws_sdk_dynamodb::types::KeySchemaElement::builder()
-                    .attribute_name("id")
-                    .key_type(aws_sdk_dynamodb::types::KeyType::Hash)
-                    .build()
-                    .unwrap(),
+        let mut item1 = HashMap::new();
+        item1.insert("id".to_string(), AttributeValue::S("txn-1".to_string()));
+        item1.insert("data".to_string(), AttributeValue::S("value1".to_string()));
+
+        let mut item2 = HashMap::new();
+        item2.insert("id".to_string(), AttributeValue::S("txn-2".to_string()));
+        item2.insert("data".to_string(), AttributeValue::S("value2".to_string()));
+
+        let txn_result = client
+            .transact_write_items()
+            .transact_items(
+                aws_sdk_dynamodb::types::TransactWriteItem::builder()
+                    .put(
+                        aws_sdk_dynamodb::types::Put::builder()
+                            .table_name("test-table")
+                            .set_item(Some(item1.clone()))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build(),
             )
-            .attribute_definitions(
-                aws_sdk_dynamodb::types::AttributeDefinition::builder()
-                    .attribute_name("id")
-                    .attribute_type(aws_sdk_dynamodb::types::ScalarAttributeType::S)
-                    .build()
-                    .unwrap(),
+            .transact_items(
+                aws_sdk_dynamodb::types::TransactWriteItem::builder()
+                    .put(
+                        aws_sdk_dynamodb::types::Put::builder()
+                            .table_name("test-table")
+                            .set_item(Some(item2.clone()))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build(),
             )
             .send()
             .await;
 
-        assert!(result.is_ok());
+        assert!(txn_result.is_ok());
 
-        // Verify we can use the table
+        // Verify items were written
+        let mut key1 = HashMap::new();
+        key1.insert("id".to_string(), AttributeValue::S("txn-1".to_string()));
+        key1.insert("data".to_string(), AttributeValue::S("value1".to_string()));
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key1))
+            .send()
+            .await;
+
+        assert!(get_result.is_ok());
+        assert!(get_result.unwrap().item.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_condition_expression_basic() {
+        let (dynamodb_client, dynamodb_store) = create_in_memory_dynamodb_client().await;
+
+        // Initialize the DynamoDB table for Slate
+        dynamodb_store
+            .create_table("test-table", &["shard_id", "sequence_number"])
+            .unwrap();
+
+        // Create a test item
         let mut item = HashMap::new();
-        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert(
+            "shard_id".to_string(),
+            AttributeValue::S("test-shard".to_string()),
+        );
+        item.insert(
+            "sequence_number".to_string(),
+            AttributeValue::S("1".to_string()),
+        );
+        item.insert(
+            "batch_id".to_string(),
+            AttributeValue::S("test-batch".to_string()),
+        );
 
-        let put_result = client
+        // First put should succeed
+        let result1 = dynamodb_client
             .put_item()
-            .table_name("new-table")
-            .set_item(Some(item))
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .condition_expression("attribute_not_exists(sequence_number)")
             .send()
             .await;
 
-        assert!(put_result.is_ok());
+        assert!(result1.is_ok(), "First put should succeed");
+
+        // Second put with same key should fail
+        let result2 = dynamodb_client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .condition_expression("attribute_not_exists(sequence_number)")
+            .send()
+            .await;
+
+        assert!(result2.is_err(), "Second put should fail due to condition");
+
+        // Verify it's the right type of error
+        match result2.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::put_item::PutItemError::ConditionalCheckFailedException(_) => {
+                // Expected error type
+            }
+            other => panic!("Expected ConditionalCheckFailedException, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_item_table_not_found() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        // Note: we don't insert the table, so it should not exist
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        let get_result = client
+            .get_item()
+            .table_name("nonexistent-table")
+            .set_key(Some(key))
+            .send()
+            .await;
+
+        assert!(get_result.is_err());
+        match get_result.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::get_item::GetItemError::ResourceNotFoundException(e) => {
+                assert_eq!(
+                    e.message(),
+                    Some("Requested resource not found: Table: nonexistent-table not found")
+                );
+            }
+            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_item_table_not_found() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        // Note: we don't insert the table, so it should not exist
+
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert(
+            "name".to_string(),
+            AttributeValue::S("test-name".to_string()),
+        );
+
+        let put_result = client
+            .put_item()
+            .table_name("nonexistent-table")
+            .set_item(Some(item))
+            .send()
+            .await;
+
+        assert!(put_result.is_err());
+        match put_result.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::put_item::PutItemError::ResourceNotFoundException(e) => {
+                assert_eq!(
+                    e.message(),
+                    Some("Requested resource not found: Table: nonexistent-table not found")
+                );
+            }
+            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_item_table_not_found() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        // Note: we don't insert the table, so it should not exist
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        let update_result = client
+            .update_item()
+            .table_name("nonexistent-table")
+            .set_key(Some(key))
+            .update_expression("SET #name = :val")
+            .expression_attribute_names("#name", "name")
+            .expression_attribute_values(":val", AttributeValue::S("new-value".to_string()))
+            .send()
+            .await;
+
+        assert!(update_result.is_err());
+        match update_result.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::update_item::UpdateItemError::ResourceNotFoundException(e) => {
+                assert_eq!(
+                    e.message(),
+                    Some("Requested resource not found: Table: nonexistent-table not found")
+                );
+            }
+            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_item_creates_if_not_exists() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("new-id".to_string()));
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .update_expression("SET #name = :val")
+            .expression_attribute_names("#name", "name")
+            .expression_attribute_values(":val", AttributeValue::S("test-name".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await
+            .unwrap();
+
+        assert!(get_result.item.is_some());
+        let item = get_result.item.unwrap();
+        assert_eq!(item.get("name").unwrap().as_s().unwrap(), "test-name");
+    }
+
+    #[tokio::test]
+    async fn test_update_item_seeds_new_item_from_key() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("pk".to_string(), AttributeValue::S("user".to_string()));
+        key.insert("sk".to_string(), AttributeValue::N("1".to_string()));
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .update_expression("SET x = :v")
+            .expression_attribute_values(":v", AttributeValue::S("hello".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+
+        let mut expected = key;
+        expected.insert("x".to_string(), AttributeValue::S("hello".to_string()));
+        assert_eq!(item, expected);
+    }
+
+    #[tokio::test]
+    async fn test_update_item_remove_on_missing_item_is_noop() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("missing".to_string()));
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .update_expression("REMOVE x")
+            .send()
+            .await
+            .unwrap();
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await
+            .unwrap();
+        assert!(get_result.item.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_item_add_and_arithmetic() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("counter".to_string()));
+
+        for _ in 0..2 {
+            client
+                .update_item()
+                .table_name("test-table")
+                .set_key(Some(key.clone()))
+                .update_expression(
+                    "ADD hits :one, tags :tags SET balance = if_not_exists(balance, :zero) + :half",
+                )
+                .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+                .expression_attribute_values(":zero", AttributeValue::N("0".to_string()))
+                .expression_attribute_values(":half", AttributeValue::N("0.5".to_string()))
+                .expression_attribute_values(":tags", AttributeValue::Ss(vec!["a".to_string()]))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(item.get("hits").unwrap().as_n().unwrap(), "2");
+        assert_eq!(item.get("balance").unwrap().as_n().unwrap(), "1");
+        assert_eq!(
+            item.get("tags").unwrap().as_ss().unwrap(),
+            &["a".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_item_rejects_key_attribute_update() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("a".to_string()));
+
+        let result = client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .update_expression("SET id = :v")
+            .expression_attribute_values(":v", AttributeValue::S("b".to_string()))
+            .send()
+            .await;
+        assert_validation_error(result);
+    }
+
+    #[tokio::test]
+    async fn test_update_item_malformed_expressions() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        for expression in [
+            "",
+            "SET",
+            "SET x",
+            "SET x =",
+            "SET x = :a,",
+            "SET x = list_append(:a, :b",
+            "SET x = list_append(:a :b)",
+            "SET x = if_not_exists(:a, :b)",
+            "SET x = nope(:a)",
+            "SET x = :a + ",
+            "SET x = :a SET y = :b",
+            "ADD x",
+            "REMOVE",
+            "DELETE x y",
+            "UPSERT x = :a",
+            "SET x = :a)",
+            "SET x = = :a",
+        ] {
+            let result = client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("a".to_string()))
+                .update_expression(expression)
+                .expression_attribute_values(":a", AttributeValue::L(vec![]))
+                .expression_attribute_values(":b", AttributeValue::L(vec![]))
+                .send()
+                .await;
+            assert_validation_error(result);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_return_values_none_returns_no_attributes() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let put = |return_values| {
+            client
+                .put_item()
+                .table_name("test-table")
+                .item("id", AttributeValue::S("a".to_string()))
+                .item("count", AttributeValue::N("1".to_string()))
+                .set_return_values(return_values)
+                .send()
+        };
+        put(None).await.unwrap();
+
+        // An old item exists for every write below
+        let output = put(None).await.unwrap();
+        assert!(output.attributes.is_none());
+        let output = put(Some(aws_sdk_dynamodb::types::ReturnValue::None))
+            .await
+            .unwrap();
+        assert!(output.attributes.is_none());
+
+        for return_values in [None, Some(aws_sdk_dynamodb::types::ReturnValue::None)] {
+            let output = client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("a".to_string()))
+                .update_expression("SET flag = :t")
+                .expression_attribute_values(":t", AttributeValue::Bool(true))
+                .set_return_values(return_values)
+                .send()
+                .await
+                .unwrap();
+            assert!(output.attributes.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_return_values_modes() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("count", AttributeValue::N("1".to_string()))
+            .item("label", AttributeValue::S("x".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let output = client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("count", AttributeValue::N("2".to_string()))
+            .item("label", AttributeValue::S("x".to_string()))
+            .return_values(aws_sdk_dynamodb::types::ReturnValue::AllOld)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(output.attributes.unwrap()["count"].as_n().unwrap(), "1");
+
+        let result = client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .return_values(aws_sdk_dynamodb::types::ReturnValue::AllNew)
+            .send()
+            .await;
+        assert_validation_error(result);
+
+        let update = |return_values| {
+            client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("a".to_string()))
+                .update_expression("ADD #c :one")
+                .expression_attribute_names("#c", "count")
+                .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+                .return_values(return_values)
+                .send()
+        };
+        let attributes = update(aws_sdk_dynamodb::types::ReturnValue::UpdatedOld)
+            .await
+            .unwrap()
+            .attributes
+            .unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes["count"].as_n().unwrap(), "2");
+
+        let attributes = update(aws_sdk_dynamodb::types::ReturnValue::UpdatedNew)
+            .await
+            .unwrap()
+            .attributes
+            .unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes["count"].as_n().unwrap(), "4");
+
+        let attributes = update(aws_sdk_dynamodb::types::ReturnValue::AllNew)
+            .await
+            .unwrap()
+            .attributes
+            .unwrap();
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes["count"].as_n().unwrap(), "5");
+    }
+
+    #[tokio::test]
+    async fn test_put_item_all_old_with_item_collection_metrics() {
+        use aws_sdk_dynamodb::types::{
+            KeyType, LocalSecondaryIndex, Projection, ProjectionType, ReturnItemCollectionMetrics,
+            ReturnValue, ScalarAttributeType,
+        };
+
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        client
+            .create_table()
+            .table_name("test-table")
+            .key_schema(key_schema_element("pk", KeyType::Hash))
+            .key_schema(key_schema_element("sk", KeyType::Range))
+            .attribute_definitions(attribute_definition("pk", ScalarAttributeType::S))
+            .attribute_definitions(attribute_definition("sk", ScalarAttributeType::S))
+            .attribute_definitions(attribute_definition("alt", ScalarAttributeType::S))
+            .local_secondary_indexes(
+                LocalSecondaryIndex::builder()
+                    .index_name("by-alt")
+                    .key_schema(key_schema_element("pk", KeyType::Hash))
+                    .key_schema(key_schema_element("alt", KeyType::Range))
+                    .projection(
+                        Projection::builder()
+                            .projection_type(ProjectionType::All)
+                            .build(),
+                    )
+                    .build()
+                    .unwrap(),
+            )
+            .billing_mode(aws_sdk_dynamodb::types::BillingMode::PayPerRequest)
+            .send()
+            .await
+            .unwrap();
+
+        let put = |version: &str| {
+            client
+                .put_item()
+                .table_name("test-table")
+                .item("pk", AttributeValue::S("p".to_string()))
+                .item("sk", AttributeValue::S("s".to_string()))
+                .item("alt", AttributeValue::S("a".to_string()))
+                .item("version", AttributeValue::N(version.to_string()))
+                .return_values(ReturnValue::AllOld)
+                .return_item_collection_metrics(ReturnItemCollectionMetrics::Size)
+                .send()
+        };
+        put("1").await.unwrap();
+        let output = put("2").await.unwrap();
+
+        assert_eq!(output.attributes.unwrap()["version"].as_n().unwrap(), "1");
+        let metrics = output.item_collection_metrics.unwrap();
+        assert_eq!(
+            metrics.item_collection_key.unwrap()["pk"],
+            AttributeValue::S("p".to_string())
+        );
+        assert_eq!(metrics.size_estimate_range_gb.unwrap(), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_item_collection_size_normalizes_numeric_partitions() {
+        let store = InMemoryDynamoDb::new();
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+        let item = |pk: &str, sk: u32| Item::builder().n("pk", pk).n("sk", sk).build();
+        store
+            .bulk_insert("test-table", [item("5", 1), item("5.0", 2), item("6", 3)])
+            .unwrap();
+
+        let store = store.store.lock().unwrap();
+        let table = &store["test-table"];
+        let one_item = dynamodb_item_size(&item("5", 1));
+        assert_eq!(
+            table.item_collection_size(&model::AttributeValue::N("5E0".to_string())),
+            2 * one_item
+        );
+        assert_eq!(
+            table.item_collection_size(&model::AttributeValue::N("6".to_string())),
+            one_item
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_table_billing_mode_validation() {
+        use aws_sdk_dynamodb::types::{
+            BillingMode, KeyType, ProvisionedThroughput, ScalarAttributeType,
+        };
+
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        let create_table = |table_name: &str| {
+            client
+                .create_table()
+                .table_name(table_name)
+                .key_schema(key_schema_element("id", KeyType::Hash))
+                .attribute_definitions(attribute_definition("id", ScalarAttributeType::S))
+        };
+        let throughput = |units| {
+            ProvisionedThroughput::builder()
+                .read_capacity_units(units)
+                .write_capacity_units(units)
+                .build()
+                .unwrap()
+        };
+
+        assert_validation_error(
+            create_table("provisioned-without-throughput")
+                .billing_mode(BillingMode::Provisioned)
+                .send()
+                .await,
+        );
+        assert_validation_error(
+            create_table("on-demand-with-throughput")
+                .billing_mode(BillingMode::PayPerRequest)
+                .provisioned_throughput(throughput(5))
+                .send()
+                .await,
+        );
+        assert_validation_error(
+            create_table("zero-throughput")
+                .billing_mode(BillingMode::Provisioned)
+                .provisioned_throughput(throughput(0))
+                .send()
+                .await,
+        );
+
+        create_table("provisioned")
+            .billing_mode(BillingMode::Provisioned)
+            .provisioned_throughput(throughput(5))
+            .send()
+            .await
+            .unwrap();
+        create_table("on-demand")
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_table_index_descriptions() {
+        use aws_sdk_dynamodb::types::{
+            BillingMode, GlobalSecondaryIndex, KeyType, LocalSecondaryIndex, Projection,
+            ProjectionType, ScalarAttributeType,
+        };
+
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        let projection = || {
+            Projection::builder()
+                .projection_type(ProjectionType::KeysOnly)
+                .build()
+        };
+        let create_table = |table_name: &str| {
+            client
+                .create_table()
+                .table_name(table_name)
+                .key_schema(key_schema_element("pk", KeyType::Hash))
+                .key_schema(key_schema_element("sk", KeyType::Range))
+                .attribute_definitions(attribute_definition("pk", ScalarAttributeType::S))
+                .attribute_definitions(attribute_definition("sk", ScalarAttributeType::S))
+                .billing_mode(BillingMode::PayPerRequest)
+        };
+
+        let plain = create_table("plain")
+            .send()
+            .await
+            .unwrap()
+            .table_description
+            .unwrap();
+        assert!(plain.local_secondary_indexes.is_none());
+        assert!(plain.global_secondary_indexes.is_none());
+
+        let indexed = create_table("indexed")
+            .attribute_definitions(attribute_definition("alt", ScalarAttributeType::S))
+            .local_secondary_indexes(
+                LocalSecondaryIndex::builder()
+                    .index_name("by-alt")
+                    .key_schema(key_schema_element("pk", KeyType::Hash))
+                    .key_schema(key_schema_element("alt", KeyType::Range))
+                    .projection(projection())
+                    .build()
+                    .unwrap(),
+            )
+            .global_secondary_indexes(
+                GlobalSecondaryIndex::builder()
+                    .index_name("by-sk")
+                    .key_schema(key_schema_element("sk", KeyType::Hash))
+                    .projection(projection())
+                    .build()
+                    .unwrap(),
+            )
+            .send()
+            .await
+            .unwrap()
+            .table_description
+            .unwrap();
+        let local = indexed.local_secondary_indexes.unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].index_name(), Some("by-alt"));
+        assert_eq!(local[0].key_schema().len(), 2);
+        let global = indexed.global_secondary_indexes.unwrap();
+        assert_eq!(global.len(), 1);
+        assert_eq!(global[0].index_name(), Some("by-sk"));
+        assert_eq!(
+            global[0].index_status(),
+            Some(&aws_sdk_dynamodb::types::IndexStatus::Active)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_item_legacy_attribute_updates() {
+        use aws_sdk_dynamodb::types::{AttributeAction, AttributeValueUpdate};
+
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("stale", AttributeValue::S("x".to_string()))
+            .item(
+                "tags",
+                AttributeValue::Ss(vec!["keep".to_string(), "drop".to_string()]),
+            )
+            .send()
+            .await
+            .unwrap();
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .attribute_updates(
+                "fresh",
+                AttributeValueUpdate::builder()
+                    .action(AttributeAction::Put)
+                    .value(AttributeValue::S("y".to_string()))
+                    .build(),
+            )
+            .attribute_updates(
+                "stale",
+                AttributeValueUpdate::builder()
+                    .action(AttributeAction::Delete)
+                    .build(),
+            )
+            .attribute_updates(
+                "tags",
+                AttributeValueUpdate::builder()
+                    .action(AttributeAction::Delete)
+                    .value(AttributeValue::Ss(vec!["drop".to_string()]))
+                    .build(),
+            )
+            .send()
+            .await
+            .unwrap();
+
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(item["fresh"].as_s().unwrap(), "y");
+        assert!(!item.contains_key("stale"));
+        assert_eq!(item["tags"].as_ss().unwrap(), &["keep".to_string()]);
+
+        let result = client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .update_expression("SET fresh = :v")
+            .expression_attribute_values(":v", AttributeValue::S("z".to_string()))
+            .attribute_updates(
+                "other",
+                AttributeValueUpdate::builder()
+                    .value(AttributeValue::S("z".to_string()))
+                    .build(),
+            )
+            .send()
+            .await;
+        assert_validation_error(result);
+    }
+
+    #[tokio::test]
+    async fn test_lease_expiry_with_manual_clock() {
+        use crate::clock::ManualClock;
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let store = InMemoryDynamoDb::new().with_clock(clock.clone());
+        store.create_table("leases", &["id"]).unwrap();
+        let client = crate::DynamoDbLocal::builder()
+            .with_backend(store)
+            .as_http_client()
+            .client()
+            .await;
+
+        let epoch = |time: SystemTime| time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let expiry = epoch(clock.now()) + 30;
+        client
+            .put_item()
+            .table_name("leases")
+            .item("id", AttributeValue::S("worker".to_string()))
+            .item("expiry", AttributeValue::N(expiry.to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let take_over = || {
+            client
+                .update_item()
+                .table_name("leases")
+                .key("id", AttributeValue::S("worker".to_string()))
+                .update_expression("SET expiry = :new_expiry")
+                .condition_expression("expiry < :now")
+                .expression_attribute_values(
+                    ":now",
+                    AttributeValue::N(epoch(clock.now()).to_string()),
+                )
+                .expression_attribute_values(
+                    ":new_expiry",
+                    AttributeValue::N((epoch(clock.now()) + 30).to_string()),
+                )
+                .send()
+        };
+
+        assert_conditional_check_failed(take_over().await);
+        clock.advance(Duration::from_secs(60));
+        take_over().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_item_modifies_existing() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert(
+            "name".to_string(),
+            AttributeValue::S("old-name".to_string()),
+        );
+        item.insert("count".to_string(), AttributeValue::N("5".to_string()));
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await
+            .unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .update_expression("SET #name = :val")
+            .expression_attribute_names("#name", "name")
+            .expression_attribute_values(":val", AttributeValue::S("new-name".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await
+            .unwrap();
+
+        let updated_item = get_result.item.unwrap();
+        assert_eq!(
+            updated_item.get("name").unwrap().as_s().unwrap(),
+            "new-name"
+        );
+        assert_eq!(updated_item.get("count").unwrap().as_n().unwrap(), "5");
+    }
+
+    #[tokio::test]
+    async fn test_update_item_multiple_attributes() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        client
+            .update_item()
+            .table_name("test-table")
+            .set_key(Some(key.clone()))
+            .update_expression("SET #name = :name, #status = :status")
+            .expression_attribute_names("#name", "name")
+            .expression_attribute_names("#status", "status")
+            .expression_attribute_values(":name", AttributeValue::S("test-name".to_string()))
+            .expression_attribute_values(":status", AttributeValue::S("active".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await
+            .unwrap();
+
+        let item = get_result.item.unwrap();
+        assert_eq!(item.get("name").unwrap().as_s().unwrap(), "test-name");
+        assert_eq!(item.get("status").unwrap().as_s().unwrap(), "active");
+    }
+
+    #[tokio::test]
+    async fn test_update_item_set_nested_path() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .item(
+                "profile",
+                AttributeValue::M(HashMap::from([
+                    ("verified".to_string(), AttributeValue::Bool(false)),
+                    (
+                        "tags".to_string(),
+                        AttributeValue::L(vec![AttributeValue::S("a".to_string())]),
+                    ),
+                ])),
+            )
+            .send()
+            .await
+            .unwrap();
+
+        let update = |expression: &str| {
+            client
+                .update_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("row".to_string()))
+                .update_expression(expression)
+                .expression_attribute_values(":true", AttributeValue::Bool(true))
+                .expression_attribute_values(":tag", AttributeValue::S("b".to_string()))
+                .send()
+        };
+
+        update("SET profile.verified = :true, profile.bio = :tag")
+            .await
+            .unwrap();
+        update("SET profile.tags[0] = :tag, profile.tags[5] = :tag")
+            .await
+            .unwrap();
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("row".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(
+            item["profile"],
+            AttributeValue::M(HashMap::from([
+                ("verified".to_string(), AttributeValue::Bool(true)),
+                ("bio".to_string(), AttributeValue::S("b".to_string())),
+                (
+                    "tags".to_string(),
+                    AttributeValue::L(vec![
+                        AttributeValue::S("b".to_string()),
+                        AttributeValue::S("b".to_string()),
+                    ]),
+                ),
+            ]))
+        );
+
+        for expression in [
+            "SET address.city = :tag",
+            "SET profile.address.city = :tag",
+            "SET profile.verified.flags = :tag",
+        ] {
+            let err = update(expression).await.unwrap_err();
+            assert_eq!(
+                err.into_service_error().meta().message(),
+                Some("The document path provided in the update expression is invalid for update"),
+                "{expression}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "query not supported yet"]
+    async fn test_query_table_not_found() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        // Note: we don't insert the table, so it should not exist
+
+        let query_result = client
+            .query()
+            .table_name("nonexistent-table")
+            .key_condition_expression("pk = :pk")
+            .expression_attribute_values(":pk", AttributeValue::S("test-key".to_string()))
+            .send()
+            .await;
+
+        assert!(query_result.is_err());
+        match query_result.unwrap_err().into_service_error() {
+            aws_sdk_dynamodb::operation::query::QueryError::ResourceNotFoundException(e) => {
+                assert!(
+                    e.message()
+                        .unwrap()
+                        .contains("Table: nonexistent-table not found")
+                );
+            }
+            other => panic!("Expected ResourceNotFoundException, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_existing_functionality_still_works() {
+        // This test ensures that existing functionality still works after our changes
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        // Put an item
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+        item.insert(
+            "name".to_string(),
+            AttributeValue::S("test-name".to_string()),
+        );
+
+        let put_result = client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await;
+
+        assert!(put_result.is_ok());
+
+        // Get the item back
+        let mut key = HashMap::new();
+        key.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        let get_result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(key))
+            .send()
+            .await;
+
+        assert!(get_result.is_ok());
+        let response = get_result.unwrap();
+        assert!(response.item.is_some());
+        assert_eq!(
+            response.item.unwrap().get("name").unwrap().as_s().unwrap(),
+            "test-name"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_table() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+
+        let result = client
+            .create_table()
+            .table_name("new-table")
+            .key_schema(
+                aws_sdk_dynamodb::types::KeySchemaElement::builder()
+                    .attribute_name("id")
+                    .key_type(aws_sdk_dynamodb::types::KeyType::Hash)
+                    .build()
+                    .unwrap(),
+            )
+            .attribute_definitions(
+                aws_sdk_dynamodb::types::AttributeDefinition::builder()
+                    .attribute_name("id")
+                    .attribute_type(aws_sdk_dynamodb::types::ScalarAttributeType::S)
+                    .build()
+                    .unwrap(),
+            )
+            .send()
+            .await;
+
+        assert!(result.is_ok());
+
+        // Verify we can use the table
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("test-id".to_string()));
+
+        let put_result = client
+            .put_item()
+            .table_name("new-table")
+            .set_item(Some(item))
+            .send()
+            .await;
+
+        assert!(put_result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_key_attribute_rejected() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S(String::new()));
+        let result = client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await;
+        assert_validation_error(result);
+
+        let result = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(item))
+            .send()
+            .await;
+        assert_validation_error(result);
+    }
+
+    #[tokio::test]
+    async fn test_key_type_mismatch_rejected() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+        client
+            .create_table()
+            .table_name("numbers")
+            .key_schema(key_schema_element(
+                "id",
+                aws_sdk_dynamodb::types::KeyType::Hash,
+            ))
+            .attribute_definitions(attribute_definition(
+                "id",
+                aws_sdk_dynamodb::types::ScalarAttributeType::N,
+            ))
+            .billing_mode(aws_sdk_dynamodb::types::BillingMode::PayPerRequest)
+            .send()
+            .await
+            .unwrap();
+
+        let result = client
+            .get_item()
+            .table_name("numbers")
+            .key("id", AttributeValue::S("1".to_string()))
+            .send()
+            .await;
+        assert_validation_error(result);
+
+        let result = client
+            .put_item()
+            .table_name("numbers")
+            .item("id", AttributeValue::S("1".to_string()))
+            .send()
+            .await;
+        assert_validation_error(result);
+
+        client
+            .put_item()
+            .table_name("numbers")
+            .item("id", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let item = client
+            .get_item()
+            .table_name("numbers")
+            .key("id", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item;
+        assert!(item.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_non_scalar_key_rejected() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        let map_key = AttributeValue::M(HashMap::from([(
+            "part".to_string(),
+            AttributeValue::S("a".to_string()),
+        )]));
+
+        let err = client
+            .put_item()
+            .table_name("test-table")
+            .item("id", map_key.clone())
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.into_service_error().meta().message(),
+            Some(
+                "One or more parameter values were invalid: Type mismatch for key id expected: S, N or B actual: M"
+            )
+        );
+
+        let result = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", map_key)
+            .send()
+            .await;
+        assert_validation_error(result);
+
+        let result = client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::Bool(true))
+            .update_expression("SET version = :v")
+            .expression_attribute_values(":v", AttributeValue::N("1".to_string()))
+            .send()
+            .await;
+        assert_validation_error(result);
+        assert_eq!(store.snapshot().items("test-table"), Some(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_empty_non_key_attribute_accepted() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        let mut item = HashMap::new();
+        item.insert("id".to_string(), AttributeValue::S("a".to_string()));
+        item.insert("note".to_string(), AttributeValue::S(String::new()));
+        client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone()))
+            .send()
+            .await
+            .unwrap();
+
+        let stored = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(stored, item);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_create_table_helper() {
+        let store = InMemoryDynamoDb::new();
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move { store.create_table("test-table", &["id"]) })
+            })
+            .collect();
+
+        let mut failures = 0;
+        for task in tasks {
+            if task.await.unwrap().is_err() {
+                failures += 1;
+            }
+        }
+        assert_eq!(failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_table_rejects_invalid_key_schema_length() {
+        let (client, _store) = create_in_memory_dynamodb_client().await;
+
+        for key_schema in [
+            vec![],
+            vec![
+                key_schema_element("a", aws_sdk_dynamodb::types::KeyType::Hash),
+                key_schema_element("b", aws_sdk_dynamodb::types::KeyType::Range),
+                key_schema_element("c", aws_sdk_dynamodb::types::KeyType::Range),
+            ],
+        ] {
+            let result = client
+                .create_table()
+                .table_name("test-table")
+                .set_key_schema(Some(key_schema))
+                .billing_mode(aws_sdk_dynamodb::types::BillingMode::PayPerRequest)
+                .send()
+                .await;
+            assert_validation_error(result);
+        }
+
+        // The helper and fixtures enforce the same limits
+        let store = InMemoryDynamoDb::new();
+        for key_schema in [&[][..], &["a", "b", "c"]] {
+            assert!(matches!(
+                store.create_table("test-table", key_schema),
+                Err(error::CreateTableError::ValidationException(_))
+            ));
+            let fixture = Fixture {
+                tables: vec![crate::fixture::FixtureTable {
+                    name: "test-table".to_string(),
+                    key_schema: key_schema.iter().map(|name| name.to_string()).collect(),
+                    items: Vec::new(),
+                }],
+            };
+            assert!(matches!(
+                store.apply_fixture(fixture),
+                Err(error::CreateTableError::ValidationException(_))
+            ));
+        }
+        assert!(store.table_summaries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_create_table() {
+        let store = InMemoryDynamoDb::new().idempotent_create_table(true);
+        let client = crate::DynamoDbLocal::builder()
+            .with_backend(store)
+            .as_http_client()
+            .client()
+            .await;
+
+        let create = |key_type| {
+            client
+                .create_table()
+                .table_name("test-table")
+                .key_schema(key_schema_element(
+                    "id",
+                    aws_sdk_dynamodb::types::KeyType::Hash,
+                ))
+                .attribute_definitions(attribute_definition("id", key_type))
+                .billing_mode(aws_sdk_dynamodb::types::BillingMode::PayPerRequest)
+                .send()
+        };
+
+        let first = create(aws_sdk_dynamodb::types::ScalarAttributeType::S)
+            .await
+            .unwrap();
+        let repeat = create(aws_sdk_dynamodb::types::ScalarAttributeType::S)
+            .await
+            .unwrap();
+        assert_eq!(
+            first.table_description().unwrap().creation_date_time(),
+            repeat.table_description().unwrap().creation_date_time()
+        );
+
+        let conflicting = create(aws_sdk_dynamodb::types::ScalarAttributeType::N).await;
+        crate::testing::assert_error_code(conflicting, "ResourceInUseException");
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_requires_create_table_operation() {
+        let store = InMemoryDynamoDb::new().with_strict_mode(true);
+        let client = crate::DynamoDbLocal::builder()
+            .with_backend(store.clone())
+            .as_http_client()
+            .client()
+            .await;
+
+        assert!(matches!(
+            store.create_table("direct-table", &["id"]),
+            Err(error::CreateTableError::ValidationException(_))
+        ));
+
+        client
+            .create_table()
+            .table_name("api-table")
+            .key_schema(key_schema_element(
+                "id",
+                aws_sdk_dynamodb::types::KeyType::Hash,
+            ))
+            .attribute_definitions(attribute_definition(
+                "id",
+                aws_sdk_dynamodb::types::ScalarAttributeType::S,
+            ))
+            .billing_mode(aws_sdk_dynamodb::types::BillingMode::PayPerRequest)
+            .send()
+            .await
+            .unwrap();
+        client
+            .put_item()
+            .table_name("api-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bulk_insert() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+
+        let items: Vec<_> = (0..10_000)
+            .map(|i| {
+                HashMap::from([
+                    (
+                        "pk".to_string(),
+                        model::AttributeValue::S(format!("p{}", i % 10)),
+                    ),
+                    ("sk".to_string(), model::AttributeValue::N(i.to_string())),
+                ])
+            })
+            .collect();
+        store.bulk_insert("test-table", items).unwrap();
+
+        assert_eq!(
+            store.store.lock().unwrap()["test-table"].items.len(),
+            10_000
+        );
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("pk", AttributeValue::S("p3".to_string()))
+            .key("sk", AttributeValue::N("9993".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item;
+        assert!(item.is_some());
+
+        assert!(matches!(
+            store.bulk_insert("missing-table", Vec::<Item>::new()),
+            Err(error::PutItemError::ResourceNotFoundException(_))
+        ));
+
+        // One bad item rejects the whole batch, and the store stays usable
+        let batch = [
+            Item::builder().s("pk", "new").n("sk", 1).build(),
+            Item::builder().s("pk", "new").build(),
+        ];
+        assert!(matches!(
+            store.bulk_insert("test-table", batch),
+            Err(error::PutItemError::ValidationException(_))
+        ));
+        let map_key = Item::builder()
+            .m("pk", Item::builder().s("a", "b").build())
+            .n("sk", 1)
+            .build();
+        assert!(matches!(
+            store.bulk_insert("test-table", [map_key]),
+            Err(error::PutItemError::ValidationException(_))
+        ));
+        assert_eq!(
+            store.table_summaries(),
+            vec![("test-table".to_string(), 10_000)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_item_projection() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("name", AttributeValue::S("reserved".to_string()))
+            .item("extra", AttributeValue::S("plain".to_string()))
+            .item("hidden", AttributeValue::S("never".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let get = || {
+            client
+                .get_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("row".to_string()))
+        };
+
+        let item = get()
+            .projection_expression("#n, extra")
+            .expression_attribute_names("#n", "name")
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        let mut names: Vec<_> = item.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["extra", "name"]);
+
+        let item = get()
+            .attributes_to_get("hidden")
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(item.keys().collect::<Vec<_>>(), ["hidden"]);
+
+        assert_validation_error(get().projection_expression("#missing").send().await);
+        assert_validation_error(get().projection_expression("name").send().await);
+        assert_validation_error(get().projection_expression("extra,").send().await);
+        assert_validation_error(
+            get()
+                .projection_expression("extra")
+                .attributes_to_get("hidden")
+                .send()
+                .await,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_item_projection_of_absent_attributes() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("row".to_string()))
+            .item("other", AttributeValue::S("plain".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let get = |id: &str| {
+            client
+                .get_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S(id.to_string()))
+                .projection_expression("#a, #b")
+                .expression_attribute_names("#a", "absent")
+                .expression_attribute_names("#b", "also_absent")
+                .send()
+        };
+
+        // The item exists, so it comes back, just without any attributes
+        let item = get("row").await.unwrap().item;
+        assert_eq!(item, Some(HashMap::new()));
+
+        assert_eq!(get("missing").await.unwrap().item, None);
+    }
+
+    #[tokio::test]
+    async fn test_numeric_keys_are_normalized() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("pk", AttributeValue::N("5.0".to_string()))
+            .item("sk", AttributeValue::N("10".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let items = store.query_partition("test-table", model::AttributeValue::N("5".to_string()));
+        assert_eq!(items.len(), 1);
+
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("pk", AttributeValue::N("5".to_string()))
+            .key("sk", AttributeValue::N("1E1".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item;
+        assert!(item.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_capacity_hint() {
+        let store = InMemoryDynamoDb::new().with_capacity_hint(4, 10_000);
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+        assert!(store.store.lock().unwrap().capacity() >= 4);
+        assert!(store.store.lock().unwrap()["test-table"].items.capacity() >= 10_000);
+
+        let items: Vec<_> = (0..10_000)
+            .map(|i| {
+                HashMap::from([
+                    ("pk".to_string(), model::AttributeValue::S("p".to_string())),
+                    ("sk".to_string(), model::AttributeValue::N(i.to_string())),
+                ])
+            })
+            .collect();
+        store.bulk_insert("test-table", items).unwrap();
+        assert_eq!(
+            store
+                .query_partition("test-table", model::AttributeValue::S("p".to_string()))
+                .len(),
+            10_000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        use aws_sdk_dynamodb::types::{
+            BillingMode, GlobalSecondaryIndex, KeyType, Projection, ProjectionType,
+            ScalarAttributeType,
+        };
+
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        let recording = store.record();
+
+        client
+            .create_table()
+            .table_name("test-table")
+            .key_schema(key_schema_element("id", KeyType::Hash))
+            .attribute_definitions(attribute_definition("id", ScalarAttributeType::S))
+            .attribute_definitions(attribute_definition("owner", ScalarAttributeType::S))
+            .global_secondary_indexes(
+                GlobalSecondaryIndex::builder()
+                    .index_name("by-owner")
+                    .key_schema(key_schema_element("owner", KeyType::Hash))
+                    .projection(
+                        Projection::builder()
+                            .projection_type(ProjectionType::Include)
+                            .non_key_attributes("counter")
+                            .build(),
+                    )
+                    .build()
+                    .unwrap(),
+            )
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await
+            .unwrap();
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("counter", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let conflict = client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .condition_expression("attribute_not_exists(id)")
+            .send()
+            .await;
+        assert_conditional_check_failed(conflict);
+        client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .update_expression("SET #c = #c + :one, tags = :tags")
+            .condition_expression("attribute_exists(id)")
+            .expression_attribute_names("#c", "counter")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(
+                ":tags",
+                AttributeValue::Ss(vec!["x".to_string(), "y".to_string()]),
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(recording.operations().len(), 4);
+
+        let json = serde_json::to_string(&recording.to_json()).unwrap();
+        let operations =
+            crate::recording::operations_from_json(&serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(operations, recording.operations());
+
+        let replayed = InMemoryDynamoDb::new();
+        let outcomes = replayed.replay(operations).await;
+        assert_eq!(
+            outcomes.iter().map(Outcome::is_ok).collect::<Vec<_>>(),
+            vec![true, true, false, true]
+        );
+        assert!(matches!(
+            &outcomes[2],
+            Outcome::PutItem(Err(error::PutItemError::ConditionalCheckFailedException(_)))
+        ));
+        let pk = model::AttributeValue::S("a".to_string());
+        assert_eq!(
+            replayed.query_partition("test-table", pk.clone()),
+            store.query_partition("test-table", pk)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        let key = Item::builder().s("id", "a").build();
+        let has_version = |version: &'static str| {
+            move |item: &Item| {
+                item.get("version") == Some(&model::AttributeValue::N(version.to_string()))
+            }
+        };
+
+        let writer = tokio::spawn(async move {
+            for version in ["1", "2"] {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                client
+                    .put_item()
+                    .table_name("test-table")
+                    .item("id", AttributeValue::S("a".to_string()))
+                    .item("version", AttributeValue::N(version.to_string()))
+                    .send()
+                    .await
+                    .unwrap();
+            }
+        });
+        let item = store
+            .wait_for(
+                "test-table",
+                key.clone(),
+                has_version("2"),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(item["version"], model::AttributeValue::N("2".to_string()));
+        writer.await.unwrap();
+
+        let result = store
+            .wait_for(
+                "test-table",
+                key,
+                has_version("3"),
+                Duration::from_millis(50),
+            )
+            .await;
+        assert_eq!(
+            result,
+            Err(WaitForError::Timeout(Duration::from_millis(50)))
+        );
+        let missing = Item::builder().s("id", "b").build();
+        assert!(
+            store
+                .wait_for("test-table", missing, |_| true, Duration::from_millis(10))
+                .await
+                .is_err()
+        );
+
+        // A key for the wrong schema fails immediately and leaves the store usable
+        let wrong_key = Item::builder().s("pk", "a").build();
+        assert!(matches!(
+            store
+                .wait_for("test-table", wrong_key, |_| true, Duration::from_secs(5))
+                .await,
+            Err(WaitForError::InvalidKey(_))
+        ));
+        assert_eq!(store.table_summaries(), vec![("test-table".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_table_summaries() {
+        let store = InMemoryDynamoDb::new();
+        assert_eq!(store.table_summaries(), vec![]);
+
+        store.create_table("orders", &["id"]).unwrap();
+        store.create_table("users", &["id"]).unwrap();
+        store.create_table("empty", &["id"]).unwrap();
+        let item = |id: u32| Item::builder().s("id", id.to_string()).build();
+        store.bulk_insert("orders", (0..3).map(item)).unwrap();
+        store.bulk_insert("users", [item(0)]).unwrap();
+
+        assert_eq!(
+            store.table_summaries(),
+            vec![
+                ("empty".to_string(), 0),
+                ("orders".to_string(), 3),
+                ("users".to_string(), 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_fixture() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        let fixture: Fixture = serde_json::from_str(
+            r#"{
+                "tables": [
+                    {
+                        "name": "users",
+                        "key_schema": ["id"],
+                        "items": [
+                            {"id": {"S": "alice"}, "tags": {"SS": ["admin"]}},
+                            {"id": {"S": "bob"}}
+                        ]
+                    },
+                    {"name": "events", "key_schema": ["pk", "sk"]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        store.apply_fixture(fixture.clone()).unwrap();
+
+        assert_eq!(
+            store.table_summaries(),
+            vec![("events".to_string(), 0), ("users".to_string(), 2)]
+        );
+        let item = client
+            .get_item()
+            .table_name("users")
+            .key("id", AttributeValue::S("alice".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(item["tags"], AttributeValue::Ss(vec!["admin".to_string()]));
+
+        assert!(matches!(
+            store.apply_fixture(fixture),
+            Err(error::CreateTableError::ResourceInUseException(_))
+        ));
+
+        // An item without its key is rejected before any table is created
+        let keyless: Fixture = serde_json::from_str(
+            r#"{
+                "tables": [
+                    {"name": "orders", "key_schema": ["id"], "items": [{"id": {"S": "1"}}]},
+                    {"name": "audit", "key_schema": ["id"], "items": [{"event": {"S": "x"}}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            store.apply_fixture(keyless),
+            Err(error::CreateTableError::ValidationException(_))
+        ));
+        assert_eq!(
+            store.table_summaries(),
+            vec![("events".to_string(), 0), ("users".to_string(), 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_diff() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        let item = |id: &str, version: &str| {
+            HashMap::from([
+                ("id".to_string(), model::AttributeValue::S(id.to_string())),
+                (
+                    "version".to_string(),
+                    model::AttributeValue::N(version.to_string()),
+                ),
+            ])
+        };
+        store
+            .bulk_insert("test-table", vec![item("a", "1"), item("b", "1")])
+            .unwrap();
+        let before = store.snapshot();
+        assert!(before.diff(&store.snapshot()).is_empty());
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("c".to_string()))
+            .item("version", AttributeValue::N("1".to_string()))
+            .send()
+            .await
+            .unwrap();
+        client
+            .update_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .update_expression("SET version = :two")
+            .expression_attribute_values(":two", AttributeValue::N("2".to_string()))
+            .send()
+            .await
+            .unwrap();
+        store.create_table("other-table", &["id"]).unwrap();
+        let after = store.snapshot();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.tables_added, vec!["other-table".to_string()]);
+        assert!(diff.tables_removed.is_empty());
+        let table = &diff.tables["test-table"];
+        assert_eq!(table.added, vec![item("c", "1")]);
+        assert!(table.removed.is_empty());
+        assert_eq!(table.changed, vec![(item("a", "1"), item("a", "2"))]);
+        assert_eq!(
+            diff.to_string(),
+            concat!(
+                "+ table other-table\n",
+                "table test-table:\n",
+                "  + {\"id\":{\"S\":\"c\"},\"version\":{\"N\":\"1\"}}\n",
+                "  ~ {\"id\":{\"S\":\"a\"},\"version\":{\"N\":\"1\"}} -> {\"id\":{\"S\":\"a\"},\"version\":{\"N\":\"2\"}}\n",
+            )
+        );
+
+        // Going backwards, the put item reads as a delete
+        let diff = after.diff(&before);
+        assert_eq!(diff.tables_removed, vec!["other-table".to_string()]);
+        assert_eq!(diff.tables["test-table"].removed, vec![item("c", "1")]);
+        assert!(diff.tables["test-table"].added.is_empty());
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let store = InMemoryDynamoDb::new();
+        store.create_table("test-table", &["id"]).unwrap();
+        let item = |version: &str| {
+            HashMap::from([
+                ("id".to_string(), model::AttributeValue::S("a".to_string())),
+                (
+                    "version".to_string(),
+                    model::AttributeValue::N(version.to_string()),
+                ),
+            ])
+        };
+        let key = HashMap::from([("id".to_string(), model::AttributeValue::S("a".to_string()))]);
+
+        assert!(
+            store
+                .compare_and_swap("test-table", key.clone(), None, item("0"))
+                .unwrap()
+        );
+        assert!(
+            !store
+                .compare_and_swap("test-table", key.clone(), None, item("0"))
+                .unwrap()
+        );
+
+        // Racing writers all expect version 0; exactly one of them wins
+        let swapped = std::thread::scope(|scope| {
+            let writers: Vec<_> = (1..=8)
+                .map(|version| {
+                    let (store, key) = (&store, key.clone());
+                    scope.spawn(move || {
+                        store
+                            .compare_and_swap(
+                                "test-table",
+                                key,
+                                Some(item("0")),
+                                item(&version.to_string()),
+                            )
+                            .unwrap()
+                    })
+                })
+                .collect();
+            writers
+                .into_iter()
+                .map(|writer| writer.join().unwrap())
+                .filter(|&swapped| swapped)
+                .count()
+        });
+        assert_eq!(swapped, 1);
+
+        assert!(matches!(
+            store.compare_and_swap("missing", key.clone(), None, item("0")),
+            Err(error::PutItemError::ResourceNotFoundException(_))
+        ));
+
+        // The new item must live at the key being swapped
+        let mut moved = item("9");
+        moved.insert("id".to_string(), model::AttributeValue::S("b".to_string()));
+        assert!(matches!(
+            store.compare_and_swap("test-table", key.clone(), None, moved),
+            Err(error::PutItemError::ValidationException(_))
+        ));
+        let mut keyless = item("9");
+        keyless.remove("id");
+        assert!(matches!(
+            store.compare_and_swap("test-table", key.clone(), None, keyless),
+            Err(error::PutItemError::ValidationException(_))
+        ));
+        assert!(matches!(
+            store.compare_and_swap("test-table", HashMap::new(), None, item("9")),
+            Err(error::PutItemError::ValidationException(_))
+        ));
+        assert_eq!(store.table_summaries(), vec![("test-table".to_string(), 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_fail_table() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("broken", &["id"]).unwrap();
+        store.create_table("healthy", &["id"]).unwrap();
+        store.fail_table(
+            "broken",
+            OperationKind::PutItem,
+            ErrorKind::ProvisionedThroughputExceeded,
+        );
+
+        let put = |table: &'static str| {
+            client
+                .put_item()
+                .table_name(table)
+                .item("id", AttributeValue::S("a".to_string()))
+                .send()
+        };
+        crate::testing::assert_error_code(
+            put("broken").await,
+            "ProvisionedThroughputExceededException",
+        );
+        put("healthy").await.unwrap();
+        client
+            .get_item()
+            .table_name("broken")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        store.clear_failures();
+        put("broken").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_pages() {
+        use futures_util::StreamExt;
+
+        let store = InMemoryDynamoDb::new();
+        store.create_table("test-table", &["id"]).unwrap();
+        let items: Vec<_> = (0..1000)
+            .map(|i| HashMap::from([("id".to_string(), model::AttributeValue::N(i.to_string()))]))
+            .collect();
+        store.bulk_insert("test-table", items).unwrap();
+
+        let stream = store.scan_stream("test-table", 100);
+        // Writes after the stream is created are not part of its snapshot
+        store
+            .bulk_insert(
+                "test-table",
+                vec![HashMap::from([(
+                    "id".to_string(),
+                    model::AttributeValue::N("1000".to_string()),
+                )])],
+            )
+            .unwrap();
+
+        let pages: Vec<_> = stream.collect().await;
+        assert_eq!(pages.len(), 10);
+        assert!(pages.iter().all(|page| page.len() == 100));
+        assert_eq!(pages.concat().len(), 1000);
+
+        assert_eq!(store.scan_stream("missing-table", 10).count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_query_partition_sorts_by_sort_key() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
+
+        for (pk, sk) in [("a", "10"), ("a", "9"), ("b", "1"), ("a", "-2.5")] {
+            let mut item = HashMap::new();
+            item.insert("pk".to_string(), AttributeValue::S(pk.to_string()));
+            item.insert("sk".to_string(), AttributeValue::N(sk.to_string()));
+            client
+                .put_item()
+                .table_name("test-table")
+                .set_item(Some(item))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        let sort_keys: Vec<_> = store
+            .query_partition("test-table", model::AttributeValue::S("a".to_string()))
+            .into_iter()
+            .map(|item| item["sk"].as_n().unwrap().clone())
+            .collect();
+        assert_eq!(sort_keys, ["-2.5", "9", "10"]);
+
+        assert!(
+            store
+                .query_partition("missing-table", model::AttributeValue::S("a".to_string()))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_query_partition_without_key_schema() {
+        let store = InMemoryDynamoDb::new();
+        store.store.lock().unwrap().insert(
+            "keyless".to_string(),
+            TableStore {
+                schema: Vec::new(),
+                attribute_types: HashMap::new(),
+                local_secondary_indexes: Vec::new(),
+                global_secondary_indexes: Vec::new(),
+                items: HashMap::new(),
+                creation_date_time: DateTime::from_secs(0),
+            },
+        );
+
+        assert!(
+            store
+                .query_partition("keyless", model::AttributeValue::S("a".to_string()))
+                .is_empty()
+        );
+        // The store is still usable afterwards
+        store.create_table("test-table", &["id"]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_table_reports_creation_time() {
+        let created_at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let backend =
+            InMemoryDynamoDb::new().with_clock(crate::clock::ManualClock::new(created_at));
+        let client = crate::DynamoDbLocal::builder()
+            .with_backend(backend)
+            .as_http_client()
+            .client()
+            .await;
+
+        let result = client
+            .create_table()
+            .table_name("new-table")
+            .key_schema(key_schema_element(
+                "id",
+                aws_sdk_dynamodb::types::KeyType::Hash,
+            ))
+            .attribute_definitions(attribute_definition(
+                "id",
+                aws_sdk_dynamodb::types::ScalarAttributeType::S,
+            ))
+            .send()
+            .await
+            .unwrap();
+
+        let description = result.table_description().unwrap();
+        assert_eq!(description.table_name(), Some("new-table"));
+        assert_eq!(
+            description.creation_date_time(),
+            Some(&aws_sdk_dynamodb::primitives::DateTime::from(created_at))
+        );
     }
 
     #[tokio::test]
     async fn test_create_table_already_exists() {
         let (client, store) = create_in_memory_dynamodb_client().await;
-        store.create_table("existing-table", &["id"]);
+        store.create_table("existing-table", &["id"]).unwrap();
 
         let result = client
             .create_table()
@@ -1072,7 +4242,7 @@ mod tests {
         let (client, store) = create_in_memory_dynamodb_client().await;
 
         // Test case (c): Query on completely empty table
-        store.create_table("empty-table", &["pk"]);
+        store.create_table("empty-table", &["pk"]).unwrap();
 
         let empty_result = client
             .query()
@@ -1087,7 +4257,7 @@ mod tests {
         assert!(empty_result.items().is_empty());
 
         // Setup table with composite key for remaining tests
-        store.create_table("test-table", &["pk", "sk"]);
+        store.create_table("test-table", &["pk", "sk"]).unwrap();
 
         // Insert test items with different partition keys
         let items = vec![
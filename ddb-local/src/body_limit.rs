@@ -0,0 +1,109 @@
+use dynamodb_local_server_sdk::server::body::{self, BoxBody};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// DynamoDB's limit on the size of a single request
+pub(crate) const DEFAULT_MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+
+/// Rejects requests whose body is larger than `max_bytes`
+///
+/// A `Content-Length` over the limit is answered with a 413 before the body is
+/// read. Other bodies are buffered up to the limit and answered with the same
+/// 413 as soon as they exceed it, so the service never decodes them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RequestBodyLimitLayer {
+    max_bytes: usize,
+}
+
+impl RequestBodyLimitLayer {
+    pub(crate) fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl<S> Layer<S> for RequestBodyLimitLayer {
+    type Service = RequestBodyLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestBodyLimit {
+            inner,
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RequestBodyLimit<S> {
+    inner: S,
+    max_bytes: usize,
+}
+
+impl<S, B> Service<http::Request<B>> for RequestBodyLimit<S>
+where
+    S: Service<http::Request<Full<Bytes>>, Response = http::Response<BoxBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let content_length = req
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let Some(length) = content_length
+            && length > self.max_bytes as u64
+        {
+            let response = too_large(Some(length), self.max_bytes);
+            return Box::pin(async move { Ok(response) });
+        }
+
+        // Take the service that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_bytes = self.max_bytes;
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let bytes = match Limited::new(body, max_bytes).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) if e.is::<LengthLimitError>() => return Ok(too_large(None, max_bytes)),
+                // The connection broke mid-body; decode what arrived and let
+                // deserialization report it
+                Err(_) => Bytes::new(),
+            };
+            inner
+                .call(http::Request::from_parts(parts, Full::new(bytes)))
+                .await
+        })
+    }
+}
+
+fn too_large(length: Option<u64>, max_bytes: usize) -> http::Response<BoxBody> {
+    let message = match length {
+        Some(length) => format!("Request size {length} exceeded the maximum of {max_bytes} bytes"),
+        None => format!("Request size exceeded the maximum of {max_bytes} bytes"),
+    };
+    let body = format!(
+        r#"{{"__type":"com.amazon.coral.validate#ValidationException","message":"{message}"}}"#
+    );
+    http::Response::builder()
+        .status(http::StatusCode::PAYLOAD_TOO_LARGE)
+        .header(http::header::CONTENT_TYPE, "application/x-amz-json-1.0")
+        .body(body::to_boxed(body))
+        .unwrap()
+}
@@ -0,0 +1,60 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time for the in-memory backend
+///
+/// Anything the backend stamps with "now" (e.g. table creation times) reads it
+/// from here, so tests can swap in a [`ManualClock`] for deterministic output.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock backed by the system wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Clones share the same underlying time, so a test can keep a handle while the
+/// backend holds another.
+///
+/// This also makes time-based conditions testable. For a lease stored as an
+/// epoch-seconds `N` attribute, read "now" from the test's handle when
+/// building `:now` for a condition like `expiry < :now`, then
+/// [`advance`](Self::advance) past the expiry: the same conditional write
+/// fails before the advance and succeeds after it.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl ManualClock {
+    /// Create a clock frozen at `start`
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, now: SystemTime) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// Move the current time forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
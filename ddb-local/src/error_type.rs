@@ -0,0 +1,123 @@
+use dynamodb_local_server_sdk::server::body::{self, BoxBody};
+use http::HeaderValue;
+use http_body_util::BodyExt;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// The namespace the generated server puts in `__type`, from the Smithy model
+const MODEL_NAMESPACE: &str = "com.amazonaws.dynamodb#";
+
+/// The namespace real DynamoDB puts in `__type`
+const WIRE_NAMESPACE: &str = "com.amazonaws.dynamodb.v20120810#";
+
+/// The namespace real DynamoDB puts in `__type` for `ValidationException`,
+/// which comes from its request validation layer rather than the service
+const VALIDATION_NAMESPACE: &str = "com.amazon.coral.validate#";
+
+/// Rewrites the `__type` of error bodies to the namespace DynamoDB uses
+///
+/// The AWS SDKs only look at the part after `#`, but clients that parse the
+/// JSON 1.0 error shape themselves may match on the full
+/// `com.amazonaws.dynamodb.v20120810#...` string, or on
+/// `com.amazon.coral.validate#ValidationException` for validation errors,
+/// which is also what the request size limit returns. Successful responses
+/// pass through untouched.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ErrorTypeLayer;
+
+impl<S> Layer<S> for ErrorTypeLayer {
+    type Service = ErrorType<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ErrorType { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ErrorType<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for ErrorType<S>
+where
+    S: Service<R, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let response = self.inner.call(req);
+        Box::pin(async move {
+            let response = response.await?;
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let (mut parts, body) = response.into_parts();
+            let bytes = body
+                .collect()
+                .await
+                .map(|collected| collected.to_bytes())
+                .unwrap_or_default();
+            let Some(rewritten) = rewrite(&bytes) else {
+                return Ok(http::Response::from_parts(parts, body::to_boxed(bytes)));
+            };
+            parts.headers.insert(
+                http::header::CONTENT_LENGTH,
+                HeaderValue::from(rewritten.len()),
+            );
+            Ok(http::Response::from_parts(parts, body::to_boxed(rewritten)))
+        })
+    }
+}
+
+/// The error body with its `__type` namespace replaced, or `None` if it
+/// doesn't carry the model namespace
+fn rewrite(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut error: Value = serde_json::from_slice(bytes).ok()?;
+    let error_type = error.get_mut("__type")?;
+    let name = error_type.as_str()?.strip_prefix(MODEL_NAMESPACE)?;
+    let namespace = match name {
+        "ValidationException" => VALIDATION_NAMESPACE,
+        _ => WIRE_NAMESPACE,
+    };
+    *error_type = Value::String(format!("{namespace}{name}"));
+    serde_json::to_vec(&error).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let rewritten =
+            rewrite(br#"{"__type":"com.amazonaws.dynamodb#ValidationException","message":"bad"}"#)
+                .unwrap();
+        assert_eq!(
+            String::from_utf8(rewritten).unwrap(),
+            r#"{"__type":"com.amazon.coral.validate#ValidationException","message":"bad"}"#
+        );
+        let rewritten =
+            rewrite(br#"{"__type":"com.amazonaws.dynamodb#ResourceNotFoundException"}"#).unwrap();
+        assert_eq!(
+            String::from_utf8(rewritten).unwrap(),
+            r#"{"__type":"com.amazonaws.dynamodb.v20120810#ResourceNotFoundException"}"#
+        );
+
+        assert_eq!(
+            rewrite(br#"{"__type":"com.amazon.coral.validate#ValidationException"}"#),
+            None
+        );
+        assert_eq!(rewrite(b"not json"), None);
+    }
+}
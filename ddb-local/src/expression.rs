@@ -0,0 +1,1031 @@
+//! Parsing and evaluation of DynamoDB condition, update and projection expressions
+
+use crate::reserved_words::is_reserved_word;
+use bigdecimal::BigDecimal;
+use dynamodb_local_server_sdk::model::{AttributeAction, AttributeValue, AttributeValueUpdate};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+type Item = HashMap<String, AttributeValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare attribute name or a `#name` placeholder
+    Name(String),
+    /// A `:value` placeholder
+    Value(String),
+    Comparator(Comparator),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    Plus,
+    Minus,
+    And,
+    Or,
+    Not,
+    Between,
+    In,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Name(name) | Token::Value(name) => f.write_str(name),
+            Token::Comparator(Comparator::Eq) => f.write_str("="),
+            Token::Comparator(Comparator::Ne) => f.write_str("<>"),
+            Token::Comparator(Comparator::Lt) => f.write_str("<"),
+            Token::Comparator(Comparator::Le) => f.write_str("<="),
+            Token::Comparator(Comparator::Gt) => f.write_str(">"),
+            Token::Comparator(Comparator::Ge) => f.write_str(">="),
+            Token::LParen => f.write_str("("),
+            Token::RParen => f.write_str(")"),
+            Token::LBracket => f.write_str("["),
+            Token::RBracket => f.write_str("]"),
+            Token::Dot => f.write_str("."),
+            Token::Comma => f.write_str(","),
+            Token::Plus => f.write_str("+"),
+            Token::Minus => f.write_str("-"),
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::Not => f.write_str("NOT"),
+            Token::Between => f.write_str("BETWEEN"),
+            Token::In => f.write_str("IN"),
+        }
+    }
+}
+
+fn tokenize(expression: &str, kind: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '[' => {
+                chars.next();
+                tokens.push(Token::LBracket);
+            }
+            ']' => {
+                chars.next();
+                tokens.push(Token::RBracket);
+            }
+            '.' => {
+                chars.next();
+                tokens.push(Token::Dot);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '+' => {
+                chars.next();
+                tokens.push(Token::Plus);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Minus);
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Comparator(Comparator::Eq));
+            }
+            '<' => {
+                chars.next();
+                let comparator = if chars.next_if_eq(&'>').is_some() {
+                    Comparator::Ne
+                } else if chars.next_if_eq(&'=').is_some() {
+                    Comparator::Le
+                } else {
+                    Comparator::Lt
+                };
+                tokens.push(Token::Comparator(comparator));
+            }
+            '>' => {
+                chars.next();
+                let comparator = if chars.next_if_eq(&'=').is_some() {
+                    Comparator::Ge
+                } else {
+                    Comparator::Gt
+                };
+                tokens.push(Token::Comparator(comparator));
+            }
+            c if c == ':' || c == '#' || c == '_' || c.is_ascii_alphanumeric() => {
+                let mut word = String::new();
+                word.push(c);
+                chars.next();
+                while let Some(c) = chars.next_if(|c| *c == '_' || c.is_ascii_alphanumeric()) {
+                    word.push(c);
+                }
+                let token = if word.starts_with(':') {
+                    Token::Value(word)
+                } else if word.eq_ignore_ascii_case("AND") {
+                    Token::And
+                } else if word.eq_ignore_ascii_case("OR") {
+                    Token::Or
+                } else if word.eq_ignore_ascii_case("NOT") {
+                    Token::Not
+                } else if word.eq_ignore_ascii_case("BETWEEN") {
+                    Token::Between
+                } else if word.eq_ignore_ascii_case("IN") {
+                    Token::In
+                } else {
+                    Token::Name(word)
+                };
+                tokens.push(token);
+            }
+            other => return Err(syntax_error(kind, other)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn syntax_error(kind: &str, token: impl fmt::Display) -> String {
+    format!("Invalid {kind}: Syntax error; token: \"{token}\"")
+}
+
+/// One step of a document path: a map key or a list index
+#[derive(Debug, Clone, PartialEq)]
+enum PathElement {
+    Attribute(String),
+    Index(usize),
+}
+
+/// A path into an item, such as `meta.verified` or `history[0]`
+#[derive(Debug, Clone, PartialEq)]
+struct DocumentPath(Vec<PathElement>);
+
+#[derive(Debug)]
+enum Operand {
+    Path(DocumentPath),
+    /// `size(path)`
+    Size(DocumentPath),
+    Value(AttributeValue),
+}
+
+#[derive(Debug)]
+enum Condition {
+    Or(Box<Condition>, Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare(Operand, Comparator, Operand),
+    Between(Operand, Operand, Operand),
+    In(Operand, Vec<Operand>),
+    AttributeExists(DocumentPath),
+    AttributeNotExists(DocumentPath),
+    BeginsWith(Operand, Operand),
+    Contains(Operand, Operand),
+}
+
+/// Right-hand side of a `SET` action
+#[derive(Debug)]
+enum UpdateValue {
+    Operand(Operand),
+    IfNotExists(String, Box<UpdateValue>),
+    ListAppend(Box<UpdateValue>, Box<UpdateValue>),
+    Plus(Box<UpdateValue>, Box<UpdateValue>),
+    Minus(Box<UpdateValue>, Box<UpdateValue>),
+}
+
+/// The actions of a parsed update expression
+#[derive(Debug, Default)]
+pub(crate) struct UpdateActions {
+    set: Vec<(DocumentPath, UpdateValue)>,
+    remove: Vec<String>,
+    add: Vec<(String, AttributeValue)>,
+    delete: Vec<(String, AttributeValue)>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    names: Option<&'a HashMap<String, String>>,
+    values: Option<&'a Item>,
+    kind: &'static str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(
+        expression: &str,
+        kind: &'static str,
+        names: Option<&'a HashMap<String, String>>,
+        values: Option<&'a Item>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            tokens: tokenize(expression, kind)?,
+            pos: 0,
+            names,
+            values,
+            kind,
+        })
+    }
+
+    fn error(&self, token: Option<Token>) -> String {
+        match token {
+            Some(token) => syntax_error(self.kind, token),
+            None => syntax_error(self.kind, "<EOF>"),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(self.error(other)),
+        }
+    }
+
+    fn parse_condition(mut self) -> Result<Condition, String> {
+        let condition = self.parse_or()?;
+        match self.peek() {
+            None => Ok(condition),
+            Some(token) => Err(self.error(Some(token.clone()))),
+        }
+    }
+
+    fn parse_projection(mut self) -> Result<Vec<String>, String> {
+        let mut paths = vec![self.parse_path()?];
+        while let Some(token) = self.next() {
+            if token != Token::Comma {
+                return Err(self.error(Some(token)));
+            }
+            paths.push(self.parse_path()?);
+        }
+        Ok(paths)
+    }
+
+    fn parse_update(mut self) -> Result<UpdateActions, String> {
+        let mut actions = UpdateActions::default();
+        let mut seen = Vec::new();
+
+        while let Some(token) = self.next() {
+            let clause = match &token {
+                Token::Name(name) => name.to_ascii_uppercase(),
+                _ => return Err(self.error(Some(token))),
+            };
+            if seen.contains(&clause) {
+                return Err(format!(
+                    "Invalid UpdateExpression: The \"{clause}\" section can only be used once in an update expression;"
+                ));
+            }
+            match clause.as_str() {
+                "SET" => loop {
+                    let path = self.parse_document_path()?;
+                    self.expect(Token::Comparator(Comparator::Eq))?;
+                    let value = self.parse_update_value()?;
+                    actions.set.push((path, value));
+                    if !self.next_if(&Token::Comma) {
+                        break;
+                    }
+                },
+                "REMOVE" => loop {
+                    actions.remove.push(self.parse_path()?);
+                    if !self.next_if(&Token::Comma) {
+                        break;
+                    }
+                },
+                "ADD" | "DELETE" => loop {
+                    let path = self.parse_path()?;
+                    let value = self.parse_value()?;
+                    if clause == "ADD" {
+                        actions.add.push((path, value));
+                    } else {
+                        actions.delete.push((path, value));
+                    }
+                    if !self.next_if(&Token::Comma) {
+                        break;
+                    }
+                },
+                _ => return Err(self.error(Some(token))),
+            }
+            seen.push(clause);
+        }
+
+        if seen.is_empty() {
+            return Err(self.error(None));
+        }
+        Ok(actions)
+    }
+
+    fn next_if(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_update_value(&mut self) -> Result<UpdateValue, String> {
+        let left = self.parse_update_operand()?;
+        if self.next_if(&Token::Plus) {
+            let right = self.parse_update_operand()?;
+            return Ok(UpdateValue::Plus(Box::new(left), Box::new(right)));
+        }
+        if self.next_if(&Token::Minus) {
+            let right = self.parse_update_operand()?;
+            return Ok(UpdateValue::Minus(Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_update_operand(&mut self) -> Result<UpdateValue, String> {
+        if let (Some(Token::Name(function)), Some(Token::LParen)) =
+            (self.tokens.get(self.pos), self.tokens.get(self.pos + 1))
+        {
+            let function = function.clone();
+            self.pos += 2;
+            let value = match function.as_str() {
+                "if_not_exists" => {
+                    let path = self.parse_path()?;
+                    self.expect(Token::Comma)?;
+                    let fallback = self.parse_update_value()?;
+                    UpdateValue::IfNotExists(path, Box::new(fallback))
+                }
+                "list_append" => {
+                    let first = self.parse_update_value()?;
+                    self.expect(Token::Comma)?;
+                    let second = self.parse_update_value()?;
+                    UpdateValue::ListAppend(Box::new(first), Box::new(second))
+                }
+                other => {
+                    return Err(format!(
+                        "Invalid UpdateExpression: Invalid function name; function: {other}"
+                    ));
+                }
+            };
+            self.expect(Token::RParen)?;
+            return Ok(value);
+        }
+        self.parse_operand().map(UpdateValue::Operand)
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            let right = self.parse_and()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, String> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            let right = self.parse_not()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Condition, String> {
+        if self.next_if(&Token::Not) {
+            return Ok(Condition::Not(Box::new(self.parse_not()?)));
+        }
+        if self.next_if(&Token::LParen) {
+            let condition = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(condition);
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Condition, String> {
+        if let (Some(Token::Name(function)), Some(Token::LParen)) =
+            (self.tokens.get(self.pos), self.tokens.get(self.pos + 1))
+            && function != "size"
+        {
+            let function = function.clone();
+            self.pos += 2;
+            let mut args = vec![self.parse_operand()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.next();
+                args.push(self.parse_operand()?);
+            }
+            self.expect(Token::RParen)?;
+            return self.function(&function, args);
+        }
+
+        let left = self.parse_operand()?;
+        match self.next() {
+            Some(Token::Comparator(comparator)) => {
+                let right = self.parse_operand()?;
+                Ok(Condition::Compare(left, comparator, right))
+            }
+            Some(Token::Between) => {
+                let low = self.parse_operand()?;
+                self.expect(Token::And)?;
+                let high = self.parse_operand()?;
+                Ok(Condition::Between(left, low, high))
+            }
+            Some(Token::In) => {
+                self.expect(Token::LParen)?;
+                let mut candidates = vec![self.parse_operand()?];
+                while self.next_if(&Token::Comma) {
+                    candidates.push(self.parse_operand()?);
+                }
+                self.expect(Token::RParen)?;
+                Ok(Condition::In(left, candidates))
+            }
+            other => Err(self.error(other)),
+        }
+    }
+
+    fn function(&self, function: &str, args: Vec<Operand>) -> Result<Condition, String> {
+        let arg_count = args.len();
+        let mut args = args.into_iter();
+        match (function, args.next(), args.next(), args.next()) {
+            ("attribute_exists", Some(Operand::Path(path)), None, None) => {
+                Ok(Condition::AttributeExists(path))
+            }
+            ("attribute_not_exists", Some(Operand::Path(path)), None, None) => {
+                Ok(Condition::AttributeNotExists(path))
+            }
+            ("begins_with", Some(a), Some(b), None) => Ok(Condition::BeginsWith(a, b)),
+            ("contains", Some(a), Some(b), None) => Ok(Condition::Contains(a, b)),
+            ("attribute_exists" | "attribute_not_exists" | "begins_with" | "contains", ..) => {
+                Err(format!(
+                    "Invalid {}: Incorrect number of operands for operator or function; operator or function: {function}, number of operands: {arg_count}",
+                    self.kind
+                ))
+            }
+            _ => Err(format!(
+                "Invalid {}: Invalid function name; function: {function}",
+                self.kind
+            )),
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, String> {
+        match (self.peek(), self.tokens.get(self.pos + 1)) {
+            (Some(Token::Value(_)), _) => self.parse_value().map(Operand::Value),
+            (Some(Token::Name(function)), Some(Token::LParen)) if function == "size" => {
+                self.pos += 2;
+                let path = self.parse_document_path()?;
+                self.expect(Token::RParen)?;
+                Ok(Operand::Size(path))
+            }
+            _ => self.parse_document_path().map(Operand::Path),
+        }
+    }
+
+    /// A top-level name followed by any number of `.name` and `[index]` steps
+    fn parse_document_path(&mut self) -> Result<DocumentPath, String> {
+        let mut elements = vec![PathElement::Attribute(self.parse_path()?)];
+        loop {
+            if self.next_if(&Token::Dot) {
+                elements.push(PathElement::Attribute(self.parse_path()?));
+            } else if self.next_if(&Token::LBracket) {
+                let index = match self.next() {
+                    Some(Token::Name(index)) => index.parse().map_err(|_| {
+                        format!(
+                            "Invalid {}: List index is not a number; index: {index}",
+                            self.kind
+                        )
+                    })?,
+                    other => return Err(self.error(other)),
+                };
+                self.expect(Token::RBracket)?;
+                elements.push(PathElement::Index(index));
+            } else {
+                return Ok(DocumentPath(elements));
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<AttributeValue, String> {
+        let placeholder = match self.next() {
+            Some(Token::Value(placeholder)) => placeholder,
+            other => return Err(self.error(other)),
+        };
+        self.values
+            .and_then(|values| values.get(&placeholder))
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "An expression attribute value used in expression is not defined; attribute value: {placeholder}"
+                )
+            })
+    }
+
+    fn parse_path(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Name(name)) if name.starts_with('#') => self
+                .names
+                .and_then(|names| names.get(&name))
+                .cloned()
+                .ok_or_else(|| {
+                    format!(
+                        "An expression attribute name used in the document path is not defined; attribute name: {name}"
+                    )
+                }),
+            Some(Token::Name(name)) if is_reserved_word(&name) => Err(format!(
+                "Invalid {}: Attribute name is a reserved keyword; reserved keyword: {name}",
+                self.kind
+            )),
+            Some(Token::Name(name)) => Ok(name),
+            other => Err(self.error(other)),
+        }
+    }
+}
+
+impl Condition {
+    fn evaluate(&self, item: Option<&Item>) -> bool {
+        match self {
+            Condition::Or(left, right) => left.evaluate(item) || right.evaluate(item),
+            Condition::And(left, right) => left.evaluate(item) && right.evaluate(item),
+            Condition::Not(condition) => !condition.evaluate(item),
+            Condition::AttributeExists(path) => path.resolve(item).is_some(),
+            Condition::AttributeNotExists(path) => path.resolve(item).is_none(),
+            Condition::BeginsWith(a, b) => match (a.resolve(item), b.resolve(item)) {
+                (Some(a), Some(b)) => begins_with(&a, &b),
+                _ => false,
+            },
+            Condition::Contains(a, b) => match (a.resolve(item), b.resolve(item)) {
+                (Some(a), Some(b)) => contains(&a, &b),
+                _ => false,
+            },
+            Condition::Compare(left, comparator, right) => {
+                let (left, right) = (left.resolve(item), right.resolve(item));
+                let (left, right) = (left.as_deref(), right.as_deref());
+                match comparator {
+                    Comparator::Eq => matches!((left, right), (Some(l), Some(r)) if equal(l, r)),
+                    Comparator::Ne => !matches!((left, right), (Some(l), Some(r)) if equal(l, r)),
+                    Comparator::Lt => ordered(left, right, Ordering::is_lt),
+                    Comparator::Le => ordered(left, right, Ordering::is_le),
+                    Comparator::Gt => ordered(left, right, Ordering::is_gt),
+                    Comparator::Ge => ordered(left, right, Ordering::is_ge),
+                }
+            }
+            Condition::Between(value, low, high) => {
+                let (Some(value), Some(low), Some(high)) =
+                    (value.resolve(item), low.resolve(item), high.resolve(item))
+                else {
+                    return false;
+                };
+                compare_values(&value, &low).is_some_and(Ordering::is_ge)
+                    && compare_values(&value, &high).is_some_and(Ordering::is_le)
+            }
+            Condition::In(value, candidates) => value.resolve(item).is_some_and(|value| {
+                candidates
+                    .iter()
+                    .filter_map(|candidate| candidate.resolve(item))
+                    .any(|candidate| equal(&value, &candidate))
+            }),
+        }
+    }
+}
+
+/// Whether both sides are present, comparable and satisfy `test`
+///
+/// As in DynamoDB there is no ordering across types: `price < :s` with a
+/// string `:s` is false rather than an error, and so is `price >= :s`. Only
+/// `<>` holds between values of different types.
+fn ordered(
+    left: Option<&AttributeValue>,
+    right: Option<&AttributeValue>,
+    test: fn(Ordering) -> bool,
+) -> bool {
+    left.zip(right)
+        .and_then(|(l, r)| compare_values(l, r))
+        .is_some_and(test)
+}
+
+/// Equality as DynamoDB sees it: numbers compare by value, so `1` equals
+/// `1.0`, and sets compare by membership regardless of order
+fn equal(a: &AttributeValue, b: &AttributeValue) -> bool {
+    match (a, b) {
+        (AttributeValue::N(_), AttributeValue::N(_)) => {
+            compare_values(a, b) == Some(Ordering::Equal)
+        }
+        (AttributeValue::Ss(a), AttributeValue::Ss(b)) => same_members(a, b, PartialEq::eq),
+        (AttributeValue::Ns(a), AttributeValue::Ns(b)) => same_members(a, b, |a, b| {
+            equal(&AttributeValue::N(a.clone()), &AttributeValue::N(b.clone()))
+        }),
+        (AttributeValue::Bs(a), AttributeValue::Bs(b)) => same_members(a, b, PartialEq::eq),
+        _ => a == b,
+    }
+}
+
+fn same_members<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> bool {
+    a.len() == b.len() && a.iter().all(|x| b.iter().any(|y| eq(x, y)))
+}
+
+fn begins_with(value: &AttributeValue, prefix: &AttributeValue) -> bool {
+    match (value, prefix) {
+        (AttributeValue::S(value), AttributeValue::S(prefix)) => value.starts_with(prefix.as_str()),
+        (AttributeValue::B(value), AttributeValue::B(prefix)) => {
+            value.as_ref().starts_with(prefix.as_ref())
+        }
+        _ => false,
+    }
+}
+
+fn contains(value: &AttributeValue, operand: &AttributeValue) -> bool {
+    match (value, operand) {
+        (AttributeValue::S(value), AttributeValue::S(operand)) => value.contains(operand.as_str()),
+        (AttributeValue::B(value), AttributeValue::B(operand)) => {
+            let (value, operand) = (value.as_ref(), operand.as_ref());
+            operand.is_empty() || value.windows(operand.len()).any(|w| w == operand)
+        }
+        (AttributeValue::Ss(set), AttributeValue::S(member)) => set.contains(member),
+        (AttributeValue::Ns(set), AttributeValue::N(_)) => set
+            .iter()
+            .any(|n| equal(&AttributeValue::N(n.clone()), operand)),
+        (AttributeValue::Bs(set), AttributeValue::B(member)) => set.contains(member),
+        (AttributeValue::L(list), member) => list.iter().any(|value| equal(value, member)),
+        _ => false,
+    }
+}
+
+impl DocumentPath {
+    /// The top-level attribute the path starts from
+    fn attribute(&self) -> &str {
+        match &self.0[0] {
+            PathElement::Attribute(name) => name,
+            PathElement::Index(_) => unreachable!("paths start with an attribute name"),
+        }
+    }
+
+    /// Write `value` at the path, which for nested paths means its parent map
+    /// or list must already exist
+    ///
+    /// A list index past the end appends, like DynamoDB does.
+    fn set(&self, item: &mut Item, value: AttributeValue) -> Result<(), String> {
+        let invalid = || {
+            "The document path provided in the update expression is invalid for update".to_string()
+        };
+        let (last, parents) = self.0.split_last().expect("paths are never empty");
+        let Some((_, steps)) = parents.split_first() else {
+            item.insert(self.attribute().to_string(), value);
+            return Ok(());
+        };
+        let mut parent = item.get_mut(self.attribute()).ok_or_else(invalid)?;
+        for step in steps {
+            parent = match (step, parent) {
+                (PathElement::Attribute(name), AttributeValue::M(map)) => {
+                    map.get_mut(name).ok_or_else(invalid)?
+                }
+                (PathElement::Index(index), AttributeValue::L(list)) => {
+                    list.get_mut(*index).ok_or_else(invalid)?
+                }
+                _ => return Err(invalid()),
+            };
+        }
+        match (last, parent) {
+            (PathElement::Attribute(name), AttributeValue::M(map)) => {
+                map.insert(name.clone(), value);
+            }
+            (PathElement::Index(index), AttributeValue::L(list)) => match list.get_mut(*index) {
+                Some(element) => *element = value,
+                None => list.push(value),
+            },
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+
+    fn resolve<'a>(&self, item: Option<&'a Item>) -> Option<&'a AttributeValue> {
+        let mut elements = self.0.iter();
+        let Some(PathElement::Attribute(name)) = elements.next() else {
+            return None;
+        };
+        let mut value = item?.get(name)?;
+        for element in elements {
+            value = match (element, value) {
+                (PathElement::Attribute(name), AttributeValue::M(map)) => map.get(name)?,
+                (PathElement::Index(index), AttributeValue::L(list)) => list.get(*index)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, item: Option<&'a Item>) -> Option<Cow<'a, AttributeValue>> {
+        match self {
+            Operand::Path(path) => path.resolve(item).map(Cow::Borrowed),
+            Operand::Size(path) => path
+                .resolve(item)
+                .and_then(size)
+                .map(|size| Cow::Owned(AttributeValue::N(size.to_string()))),
+            Operand::Value(value) => Some(Cow::Borrowed(value)),
+        }
+    }
+}
+
+/// What `size()` reports: characters of a string, bytes of a binary value and
+/// elements of a set, list or map; other types have no size
+fn size(value: &AttributeValue) -> Option<usize> {
+    match value {
+        AttributeValue::S(s) => Some(s.chars().count()),
+        AttributeValue::B(b) => Some(b.as_ref().len()),
+        AttributeValue::Ss(set) | AttributeValue::Ns(set) => Some(set.len()),
+        AttributeValue::Bs(set) => Some(set.len()),
+        AttributeValue::L(list) => Some(list.len()),
+        AttributeValue::M(map) => Some(map.len()),
+        AttributeValue::N(_) | AttributeValue::Bool(_) | AttributeValue::Null(_) => None,
+    }
+}
+
+/// Order two scalar values the way DynamoDB orders sort keys
+///
+/// Numbers compare numerically, strings and binary values byte-wise. Values of
+/// different (or non-scalar) types are unordered.
+pub(crate) fn compare_values(a: &AttributeValue, b: &AttributeValue) -> Option<Ordering> {
+    match (a, b) {
+        (AttributeValue::S(a), AttributeValue::S(b)) => Some(a.as_bytes().cmp(b.as_bytes())),
+        (AttributeValue::N(a), AttributeValue::N(b)) => {
+            let a: BigDecimal = a.parse().ok()?;
+            let b: BigDecimal = b.parse().ok()?;
+            Some(a.cmp(&b))
+        }
+        (AttributeValue::B(a), AttributeValue::B(b)) => Some(a.as_ref().cmp(b.as_ref())),
+        _ => None,
+    }
+}
+
+fn incorrect_operand_type() -> String {
+    "An operand in the update expression has an incorrect data type".to_string()
+}
+
+fn add_numbers(a: &str, b: &str, negate_b: bool) -> Result<String, String> {
+    let a: BigDecimal = a.parse().map_err(|_| incorrect_operand_type())?;
+    let b: BigDecimal = b.parse().map_err(|_| incorrect_operand_type())?;
+    let sum = if negate_b { a - b } else { a + b };
+    Ok(sum.normalized().to_plain_string())
+}
+
+impl UpdateValue {
+    /// Compute the value against the item as it was before the update
+    fn evaluate(&self, item: &Item) -> Result<AttributeValue, String> {
+        match self {
+            UpdateValue::Operand(operand) => operand
+                .resolve(Some(item))
+                .map(Cow::into_owned)
+                .ok_or_else(|| {
+                    "The provided expression refers to an attribute that does not exist in the item"
+                        .to_string()
+                }),
+            UpdateValue::IfNotExists(path, fallback) => match item.get(path) {
+                Some(value) => Ok(value.clone()),
+                None => fallback.evaluate(item),
+            },
+            UpdateValue::ListAppend(first, second) => {
+                match (first.evaluate(item)?, second.evaluate(item)?) {
+                    (AttributeValue::L(mut first), AttributeValue::L(second)) => {
+                        first.extend(second);
+                        Ok(AttributeValue::L(first))
+                    }
+                    _ => Err(incorrect_operand_type()),
+                }
+            }
+            UpdateValue::Plus(a, b) | UpdateValue::Minus(a, b) => {
+                match (a.evaluate(item)?, b.evaluate(item)?) {
+                    (AttributeValue::N(a), AttributeValue::N(b)) => Ok(AttributeValue::N(
+                        add_numbers(&a, &b, matches!(self, UpdateValue::Minus(..)))?,
+                    )),
+                    _ => Err(incorrect_operand_type()),
+                }
+            }
+        }
+    }
+}
+
+impl UpdateActions {
+    /// Whether applying these actions to a missing item should create it
+    ///
+    /// `REMOVE` and `DELETE` on their own leave a nonexistent item absent.
+    pub(crate) fn creates_item(&self) -> bool {
+        !self.set.is_empty() || !self.add.is_empty()
+    }
+
+    /// Names of all top-level attributes the actions modify
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &str> {
+        self.set
+            .iter()
+            .map(|(path, _)| path.attribute())
+            .chain(self.remove.iter().map(String::as_str))
+            .chain(self.add.iter().map(|(path, _)| path.as_str()))
+            .chain(self.delete.iter().map(|(path, _)| path.as_str()))
+    }
+
+    pub(crate) fn apply(&self, item: &mut Item) -> Result<(), String> {
+        let original = item.clone();
+
+        for (path, value) in &self.set {
+            let value = value.evaluate(&original)?;
+            path.set(item, value)?;
+        }
+
+        for path in &self.remove {
+            item.remove(path);
+        }
+
+        for (path, value) in &self.add {
+            let updated = match (item.remove(path), value) {
+                (None, value) => value.clone(),
+                (Some(AttributeValue::N(a)), AttributeValue::N(b)) => {
+                    AttributeValue::N(add_numbers(&a, b, false)?)
+                }
+                (Some(AttributeValue::Ss(mut set)), AttributeValue::Ss(members)) => {
+                    union(&mut set, members);
+                    AttributeValue::Ss(set)
+                }
+                (Some(AttributeValue::Ns(mut set)), AttributeValue::Ns(members)) => {
+                    union(&mut set, members);
+                    AttributeValue::Ns(set)
+                }
+                (Some(AttributeValue::Bs(mut set)), AttributeValue::Bs(members)) => {
+                    union(&mut set, members);
+                    AttributeValue::Bs(set)
+                }
+                _ => return Err(incorrect_operand_type()),
+            };
+            item.insert(path.clone(), updated);
+        }
+
+        for (path, value) in &self.delete {
+            let Some(existing) = item.remove(path) else {
+                continue;
+            };
+            let remaining = match (existing, value) {
+                (AttributeValue::Ss(mut set), AttributeValue::Ss(members)) => {
+                    set.retain(|m| !members.contains(m));
+                    (!set.is_empty()).then_some(AttributeValue::Ss(set))
+                }
+                (AttributeValue::Ns(mut set), AttributeValue::Ns(members)) => {
+                    set.retain(|m| !members.contains(m));
+                    (!set.is_empty()).then_some(AttributeValue::Ns(set))
+                }
+                (AttributeValue::Bs(mut set), AttributeValue::Bs(members)) => {
+                    set.retain(|m| !members.contains(m));
+                    (!set.is_empty()).then_some(AttributeValue::Bs(set))
+                }
+                _ => return Err(incorrect_operand_type()),
+            };
+            if let Some(remaining) = remaining {
+                item.insert(path.clone(), remaining);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn union<T: PartialEq + Clone>(set: &mut Vec<T>, members: &[T]) {
+    for member in members {
+        if !set.contains(member) {
+            set.push(member.clone());
+        }
+    }
+}
+
+/// Evaluate `expression` against `item` (the item currently stored, if any)
+///
+/// Returns an error message suitable for a `ValidationException` if the
+/// expression is malformed or references an undefined placeholder.
+pub(crate) fn evaluate_condition_expression(
+    expression: &str,
+    item: Option<&Item>,
+    expression_attribute_names: Option<&HashMap<String, String>>,
+    expression_attribute_values: Option<&Item>,
+) -> Result<bool, String> {
+    let condition = Parser::new(
+        expression,
+        "ConditionExpression",
+        expression_attribute_names,
+        expression_attribute_values,
+    )?
+    .parse_condition()?;
+    Ok(condition.evaluate(item))
+}
+
+/// Translate the legacy `AttributeUpdates` parameter into update actions
+///
+/// `PUT` (the default action) sets the attribute, `ADD` behaves like the
+/// `ADD` clause, and `DELETE` removes the attribute, or only the given members
+/// when a set value is supplied.
+pub(crate) fn attribute_updates_to_actions(
+    attribute_updates: &HashMap<String, AttributeValueUpdate>,
+) -> Result<UpdateActions, String> {
+    let mut actions = UpdateActions::default();
+    for (name, update) in attribute_updates {
+        match (
+            update.action.as_ref().unwrap_or(&AttributeAction::Put),
+            &update.value,
+        ) {
+            (AttributeAction::Put, Some(value)) => actions.set.push((
+                DocumentPath(vec![PathElement::Attribute(name.clone())]),
+                UpdateValue::Operand(Operand::Value(value.clone())),
+            )),
+            (AttributeAction::Add, Some(value)) => actions.add.push((name.clone(), value.clone())),
+            (AttributeAction::Delete, Some(value)) => {
+                actions.delete.push((name.clone(), value.clone()))
+            }
+            (AttributeAction::Delete, None) => actions.remove.push(name.clone()),
+            (action, None) => {
+                return Err(format!(
+                    "One or more parameter values were invalid: Only DELETE action is allowed when no attribute value is specified; attribute: {name}, action: {}",
+                    action.as_str()
+                ));
+            }
+        }
+    }
+    Ok(actions)
+}
+
+/// Parse an update expression into the actions it describes
+pub(crate) fn parse_update_expression(
+    expression: &str,
+    expression_attribute_names: Option<&HashMap<String, String>>,
+    expression_attribute_values: Option<&Item>,
+) -> Result<UpdateActions, String> {
+    Parser::new(
+        expression,
+        "UpdateExpression",
+        expression_attribute_names,
+        expression_attribute_values,
+    )?
+    .parse_update()
+}
+
+/// Parse a projection expression into the attribute names it selects
+pub(crate) fn parse_projection_expression(
+    expression: &str,
+    expression_attribute_names: Option<&HashMap<String, String>>,
+) -> Result<Vec<String>, String> {
+    Parser::new(
+        expression,
+        "ProjectionExpression",
+        expression_attribute_names,
+        None,
+    )?
+    .parse_projection()
+}
+
+/// The attributes to return for a read, from `ProjectionExpression` or the
+/// legacy `AttributesToGet`
+///
+/// `None` means the whole item. Every read that supports projection should go
+/// through here, so `#name` placeholders resolve the same way everywhere.
+pub(crate) fn projection(
+    projection_expression: Option<&str>,
+    attributes_to_get: Option<&[String]>,
+    expression_attribute_names: Option<&HashMap<String, String>>,
+) -> Result<Option<Vec<String>>, String> {
+    match (projection_expression, attributes_to_get) {
+        (Some(_), Some(_)) => Err(
+            "Can not use both expression and non-expression parameters in the same request: Non-expression parameters: {AttributesToGet} Expression parameters: {ProjectionExpression}"
+                .to_string(),
+        ),
+        (Some(expression), None) => {
+            parse_projection_expression(expression, expression_attribute_names).map(Some)
+        }
+        (None, Some(attributes)) => Ok(Some(attributes.to_vec())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Keep only the attributes of `item` named in `paths`
+pub(crate) fn project(mut item: Item, paths: &[String]) -> Item {
+    item.retain(|name, _| paths.contains(name));
+    item
+}
@@ -0,0 +1,217 @@
+use crate::DynamoDb;
+use crate::backend::InMemoryDynamoDb;
+use crate::item::{from_sdk, to_sdk};
+use dynamodb_local_server_sdk::model::AttributeValue;
+use dynamodb_local_server_sdk::{error, input, output};
+use std::collections::HashMap;
+
+/// Backend that reads through to a real DynamoDB on a local miss
+///
+/// Reads are served from `local`. When `GetItem` finds no item, the same key
+/// is fetched from `remote` and, if found, cached in `local` so later reads
+/// never leave the process. Writes only ever touch `local`, and they don't
+/// read through: updating a key that exists only remotely starts from scratch.
+///
+/// Tables must exist in `local`; a missing local table is still a
+/// `ResourceNotFoundException`. A table missing from `remote` is treated as
+/// a miss there.
+#[derive(Clone)]
+pub struct FallbackDynamoDb {
+    local: InMemoryDynamoDb,
+    remote: aws_sdk_dynamodb::Client,
+}
+
+impl FallbackDynamoDb {
+    pub fn new(local: InMemoryDynamoDb, remote: aws_sdk_dynamodb::Client) -> Self {
+        Self { local, remote }
+    }
+
+    async fn fetch_remote(
+        &self,
+        input: &input::GetItemInput,
+    ) -> Result<Option<HashMap<String, AttributeValue>>, error::InternalServerError> {
+        let key = input
+            .key
+            .iter()
+            .map(|(name, value)| (name.clone(), to_sdk(value)))
+            .collect();
+        let result = self
+            .remote
+            .get_item()
+            .table_name(&input.table_name)
+            .set_key(Some(key))
+            .set_consistent_read(input.consistent_read)
+            .send()
+            .await;
+        let output = match result {
+            Ok(output) => output,
+            // The table only exists locally, so there is nothing to read through to
+            Err(e)
+                if e.as_service_error()
+                    .is_some_and(|e| e.is_resource_not_found_exception()) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(error::InternalServerError::builder()
+                    .message(Some(format!("remote GetItem failed: {e}")))
+                    .build());
+            }
+        };
+
+        output
+            .item
+            .map(|item| {
+                item.into_iter()
+                    .map(|(name, value)| {
+                        let value = from_sdk(value).map_err(|e| {
+                            error::InternalServerError::builder()
+                                .message(Some(format!(
+                                    "unsupported remote attribute value: {}",
+                                    e.0
+                                )))
+                                .build()
+                        })?;
+                        Ok((name, value))
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+#[async_trait::async_trait]
+impl DynamoDb for FallbackDynamoDb {
+    async fn get_item(
+        &self,
+        input: input::GetItemInput,
+    ) -> Result<output::GetItemOutput, error::GetItemError> {
+        let local = self.local.get_item(input.clone()).await?;
+        if local.item.is_some() {
+            return Ok(local);
+        }
+
+        let Some(item) = self
+            .fetch_remote(&input)
+            .await
+            .map_err(error::GetItemError::InternalServerError)?
+        else {
+            return Ok(local);
+        };
+        self.local.cache_item(&input.table_name, item);
+        self.local.get_item(input).await
+    }
+
+    async fn put_item(
+        &self,
+        input: input::PutItemInput,
+    ) -> Result<output::PutItemOutput, error::PutItemError> {
+        self.local.put_item(input).await
+    }
+
+    async fn create_table(
+        &self,
+        input: input::CreateTableInput,
+    ) -> Result<output::CreateTableOutput, error::CreateTableError> {
+        DynamoDb::create_table(&self.local, input).await
+    }
+
+    async fn update_item(
+        &self,
+        input: input::UpdateItemInput,
+    ) -> Result<output::UpdateItemOutput, error::UpdateItemError> {
+        self.local.update_item(input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DynamoDbLocal;
+    use aws_sdk_dynamodb::types::AttributeValue;
+
+    #[tokio::test]
+    async fn test_reads_through_and_caches() {
+        let remote_store = InMemoryDynamoDb::new();
+        remote_store.create_table("test-table", &["id"]).unwrap();
+        let remote = DynamoDbLocal::builder()
+            .with_backend(remote_store)
+            .as_http_client()
+            .client()
+            .await;
+        remote
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("origin", AttributeValue::S("remote".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let local = InMemoryDynamoDb::new();
+        local.create_table("test-table", &["id"]).unwrap();
+        let client = DynamoDbLocal::builder()
+            .with_backend(FallbackDynamoDb::new(local, remote.clone()))
+            .as_http_client()
+            .client()
+            .await;
+
+        let get = || {
+            client
+                .get_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("a".to_string()))
+                .send()
+        };
+        let item = get().await.unwrap().item.unwrap();
+        assert_eq!(item["origin"].as_s().unwrap(), "remote");
+
+        // Change the remote copy; the cached local copy must win from now on
+        remote
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .item("origin", AttributeValue::S("changed".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let item = get().await.unwrap().item.unwrap();
+        assert_eq!(item["origin"].as_s().unwrap(), "remote");
+
+        // Misses on both sides are still misses
+        let missing = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("missing".to_string()))
+            .send()
+            .await
+            .unwrap();
+        assert!(missing.item.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_missing_remote_table_is_a_miss() {
+        let remote = DynamoDbLocal::builder()
+            .with_backend(InMemoryDynamoDb::new())
+            .as_http_client()
+            .client()
+            .await;
+
+        let local = InMemoryDynamoDb::new();
+        local.create_table("local-only", &["id"]).unwrap();
+        let client = DynamoDbLocal::builder()
+            .with_backend(FallbackDynamoDb::new(local, remote))
+            .as_http_client()
+            .client()
+            .await;
+
+        let missing = client
+            .get_item()
+            .table_name("local-only")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap();
+        assert!(missing.item.is_none());
+    }
+}
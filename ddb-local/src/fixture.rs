@@ -0,0 +1,70 @@
+//! Tables and items to seed a store with, loaded from JSON or built in code
+//!
+//! Items are written in DynamoDB JSON, as they appear on the wire:
+//!
+//! ```
+//! use ddb_local::backend::InMemoryDynamoDb;
+//! use ddb_local::fixture::Fixture;
+//!
+//! let fixture: Fixture = serde_json::from_str(
+//!     r#"{
+//!         "tables": [{
+//!             "name": "users",
+//!             "key_schema": ["id"],
+//!             "items": [{"id": {"S": "alice"}, "age": {"N": "30"}}]
+//!         }]
+//!     }"#,
+//! )
+//! .unwrap();
+//!
+//! let store = InMemoryDynamoDb::new();
+//! store.apply_fixture(fixture).unwrap();
+//! assert_eq!(store.table_summaries(), vec![("users".to_string(), 1)]);
+//! ```
+
+use crate::item::Item;
+use crate::json::json_to_item;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Tables to create, each with the items to put in it
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Fixture {
+    pub tables: Vec<FixtureTable>,
+}
+
+/// One table of a [`Fixture`]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FixtureTable {
+    pub name: String,
+    /// Key attribute names: the partition key, then the sort key if any
+    pub key_schema: Vec<String>,
+    /// Items in DynamoDB JSON, each including the key attributes
+    #[serde(default, deserialize_with = "items")]
+    pub items: Vec<Item>,
+}
+
+fn items<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Item>, D::Error> {
+    Vec::<Value>::deserialize(deserializer)?
+        .iter()
+        .map(|item| {
+            json_to_item(item)
+                .map(Item::from)
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_item_fails_to_deserialize() {
+        let err = serde_json::from_str::<Fixture>(
+            r#"{"tables": [{"name": "t", "key_schema": ["id"], "items": [{"id": {"X": "1"}}]}]}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid DynamoDB JSON"), "{err}");
+    }
+}
@@ -0,0 +1,345 @@
+//! A terser way to write items in tests
+//!
+//! ```
+//! use ddb_local::item::Item;
+//!
+//! let item = Item::builder().s("id", "x").n("hits", 5).bool("active", true).build();
+//! assert_eq!(item.len(), 3);
+//! ```
+//!
+//! [`dynamodb_item_size`] computes an item's size the way DynamoDB does.
+//!
+//! An [`Item`] converts into the item maps of both the server model (for the
+//! backend's helpers such as [`bulk_insert`]) and the AWS SDK client.
+//!
+//! [`bulk_insert`]: crate::backend::InMemoryDynamoDb::bulk_insert
+
+use aws_sdk_dynamodb::types::AttributeValue as SdkAttributeValue;
+use aws_smithy_types::Blob;
+use dynamodb_local_server_sdk::model::AttributeValue;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+
+/// An item: attribute names mapped to their values
+///
+/// Derefs to the underlying map, so lookups work as they would on a
+/// `HashMap`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Item(HashMap<String, AttributeValue>);
+
+impl Item {
+    pub fn builder() -> ItemBuilder {
+        ItemBuilder::default()
+    }
+
+    pub fn into_inner(self) -> HashMap<String, AttributeValue> {
+        self.0
+    }
+}
+
+impl Deref for Item {
+    type Target = HashMap<String, AttributeValue>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Builder for [`Item`], one method per attribute type
+///
+/// Setting the same name twice keeps the last value.
+#[derive(Debug, Clone, Default)]
+pub struct ItemBuilder {
+    attributes: HashMap<String, AttributeValue>,
+}
+
+impl ItemBuilder {
+    /// Set `name` to any attribute value
+    pub fn attribute(mut self, name: impl Into<String>, value: AttributeValue) -> Self {
+        self.attributes.insert(name.into(), value);
+        self
+    }
+
+    pub fn s(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attribute(name, AttributeValue::S(value.into()))
+    }
+
+    /// Set a number; anything `Display` works, e.g. `5`, `1.5` or `"10"`
+    pub fn n(self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.attribute(name, AttributeValue::N(value.to_string()))
+    }
+
+    pub fn b(self, name: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        self.attribute(name, AttributeValue::B(Blob::new(value)))
+    }
+
+    pub fn bool(self, name: impl Into<String>, value: bool) -> Self {
+        self.attribute(name, AttributeValue::Bool(value))
+    }
+
+    pub fn null(self, name: impl Into<String>) -> Self {
+        self.attribute(name, AttributeValue::Null(true))
+    }
+
+    pub fn ss<S: Into<String>>(
+        self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.attribute(name, AttributeValue::Ss(values))
+    }
+
+    pub fn ns<N: fmt::Display>(
+        self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = N>,
+    ) -> Self {
+        let values = values.into_iter().map(|value| value.to_string()).collect();
+        self.attribute(name, AttributeValue::Ns(values))
+    }
+
+    pub fn l(self, name: impl Into<String>, values: Vec<AttributeValue>) -> Self {
+        self.attribute(name, AttributeValue::L(values))
+    }
+
+    /// Set a nested map, itself written as an [`Item`]
+    pub fn m(self, name: impl Into<String>, value: Item) -> Self {
+        self.attribute(name, AttributeValue::M(value.0))
+    }
+
+    pub fn build(self) -> Item {
+        Item(self.attributes)
+    }
+}
+
+impl From<HashMap<String, AttributeValue>> for Item {
+    fn from(attributes: HashMap<String, AttributeValue>) -> Self {
+        Item(attributes)
+    }
+}
+
+impl From<Item> for HashMap<String, AttributeValue> {
+    fn from(item: Item) -> Self {
+        item.0
+    }
+}
+
+impl From<Item> for HashMap<String, SdkAttributeValue> {
+    fn from(item: Item) -> Self {
+        item.0
+            .iter()
+            .map(|(name, value)| (name.clone(), to_sdk(value)))
+            .collect()
+    }
+}
+
+impl TryFrom<HashMap<String, SdkAttributeValue>> for Item {
+    type Error = UnsupportedAttributeValue;
+
+    fn try_from(attributes: HashMap<String, SdkAttributeValue>) -> Result<Self, Self::Error> {
+        attributes
+            .into_iter()
+            .map(|(name, value)| Ok((name, from_sdk(value)?)))
+            .collect::<Result<_, _>>()
+            .map(Item)
+    }
+}
+
+/// Size of `item` in bytes as DynamoDB counts it toward limits and capacity
+///
+/// Follows the documented rules: each attribute costs the UTF-8 length of its
+/// name plus its value. Strings count their UTF-8 bytes and binary values
+/// their raw bytes. Numbers cost one byte per two significant digits plus
+/// one, with leading and trailing zeros trimmed. Booleans and nulls cost one
+/// byte. Sets sum their elements. Lists and maps add 3 bytes plus 1 byte per
+/// element.
+pub fn dynamodb_item_size(item: &HashMap<String, AttributeValue>) -> usize {
+    item.iter()
+        .map(|(name, value)| name.len() + value_size(value))
+        .sum()
+}
+
+fn value_size(value: &AttributeValue) -> usize {
+    match value {
+        AttributeValue::S(s) => s.len(),
+        AttributeValue::N(n) => number_size(n),
+        AttributeValue::B(b) => b.as_ref().len(),
+        AttributeValue::Bool(_) | AttributeValue::Null(_) => 1,
+        AttributeValue::Ss(set) => set.iter().map(String::len).sum(),
+        AttributeValue::Ns(set) => set.iter().map(|n| number_size(n)).sum(),
+        AttributeValue::Bs(set) => set.iter().map(|b| b.as_ref().len()).sum(),
+        AttributeValue::L(list) => 3 + list.iter().map(|v| 1 + value_size(v)).sum::<usize>(),
+        AttributeValue::M(map) => 3 + dynamodb_item_size(map) + map.len(),
+    }
+}
+
+fn number_size(n: &str) -> usize {
+    let mantissa = n.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    significant.len().div_ceil(2) + 1
+}
+
+/// Error returned for an SDK attribute value this crate doesn't model
+///
+/// The SDK's `AttributeValue` is non-exhaustive, so newer variants can't be
+/// converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedAttributeValue(pub(crate) String);
+
+impl fmt::Display for UnsupportedAttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported attribute value: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedAttributeValue {}
+
+pub(crate) fn to_sdk(value: &AttributeValue) -> SdkAttributeValue {
+    match value {
+        AttributeValue::S(s) => SdkAttributeValue::S(s.clone()),
+        AttributeValue::N(n) => SdkAttributeValue::N(n.clone()),
+        AttributeValue::B(b) => SdkAttributeValue::B(Blob::new(b.as_ref())),
+        AttributeValue::Bool(b) => SdkAttributeValue::Bool(*b),
+        AttributeValue::Null(b) => SdkAttributeValue::Null(*b),
+        AttributeValue::Ss(set) => SdkAttributeValue::Ss(set.clone()),
+        AttributeValue::Ns(set) => SdkAttributeValue::Ns(set.clone()),
+        AttributeValue::Bs(set) => {
+            SdkAttributeValue::Bs(set.iter().map(|b| Blob::new(b.as_ref())).collect())
+        }
+        AttributeValue::L(list) => SdkAttributeValue::L(list.iter().map(to_sdk).collect()),
+        AttributeValue::M(map) => SdkAttributeValue::M(
+            map.iter()
+                .map(|(name, value)| (name.clone(), to_sdk(value)))
+                .collect(),
+        ),
+    }
+}
+
+pub(crate) fn from_sdk(
+    value: SdkAttributeValue,
+) -> Result<AttributeValue, UnsupportedAttributeValue> {
+    Ok(match value {
+        SdkAttributeValue::S(s) => AttributeValue::S(s),
+        SdkAttributeValue::N(n) => AttributeValue::N(n),
+        SdkAttributeValue::B(b) => AttributeValue::B(Blob::new(b.into_inner())),
+        SdkAttributeValue::Bool(b) => AttributeValue::Bool(b),
+        SdkAttributeValue::Null(b) => AttributeValue::Null(b),
+        SdkAttributeValue::Ss(set) => AttributeValue::Ss(set),
+        SdkAttributeValue::Ns(set) => AttributeValue::Ns(set),
+        SdkAttributeValue::Bs(set) => {
+            AttributeValue::Bs(set.into_iter().map(|b| Blob::new(b.into_inner())).collect())
+        }
+        SdkAttributeValue::L(list) => {
+            AttributeValue::L(list.into_iter().map(from_sdk).collect::<Result<_, _>>()?)
+        }
+        SdkAttributeValue::M(map) => AttributeValue::M(
+            map.into_iter()
+                .map(|(name, value)| from_sdk(value).map(|value| (name, value)))
+                .collect::<Result<_, _>>()?,
+        ),
+        other => return Err(UnsupportedAttributeValue(format!("{other:?}"))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::create_in_memory_dynamodb_client;
+
+    #[test]
+    fn test_builder() {
+        let item = Item::builder()
+            .s("id", "x")
+            .n("hits", 5)
+            .ns("scores", [1.5, 2.0])
+            .null("gone")
+            .m("nested", Item::builder().bool("flag", true).build())
+            .build();
+        assert_eq!(item["id"], AttributeValue::S("x".to_string()));
+        assert_eq!(item["hits"], AttributeValue::N("5".to_string()));
+        assert_eq!(
+            item["scores"],
+            AttributeValue::Ns(vec!["1.5".to_string(), "2".to_string()])
+        );
+        assert_eq!(item["gone"], AttributeValue::Null(true));
+        assert_eq!(
+            item["nested"],
+            AttributeValue::M(HashMap::from([(
+                "flag".to_string(),
+                AttributeValue::Bool(true)
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_dynamodb_item_size() {
+        let size = |item: Item| dynamodb_item_size(&item);
+        assert_eq!(size(Item::builder().s("id", "abc").build()), 5);
+        assert_eq!(size(Item::builder().s("id", "é").build()), 4);
+
+        assert_eq!(size(Item::builder().n("n", "123.45").build()), 5);
+        assert_eq!(size(Item::builder().n("n", "-0.0100").build()), 3);
+        assert_eq!(size(Item::builder().n("n", "1E+10").build()), 3);
+        assert_eq!(size(Item::builder().n("n", 0).build()), 2);
+
+        assert_eq!(size(Item::builder().b("b", vec![0; 10]).build()), 11);
+        assert_eq!(size(Item::builder().bool("t", true).null("z").build()), 4);
+
+        let nested = Item::builder()
+            .m(
+                "m",
+                Item::builder()
+                    .s("a", "xy")
+                    .l(
+                        "l",
+                        vec![
+                            AttributeValue::N("1".to_string()),
+                            AttributeValue::Bool(true),
+                        ],
+                    )
+                    .build(),
+            )
+            .build();
+        // "a": 1 + 2; "l": 1 + 3 + (1 + 2) + (1 + 1); "m": 1 + 3 + 12 + 2
+        assert_eq!(size(nested), 18);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_through_put_and_get() {
+        let (client, store) = create_in_memory_dynamodb_client().await;
+        store.create_table("test-table", &["id"]).unwrap();
+        let item = Item::builder()
+            .s("id", "x")
+            .n("hits", 5)
+            .b("payload", *b"\x00\x01")
+            .ss("tags", ["a", "b"])
+            .l("history", vec![AttributeValue::N("1".to_string())])
+            .build();
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .set_item(Some(item.clone().into()))
+            .send()
+            .await
+            .unwrap();
+        let fetched = client
+            .get_item()
+            .table_name("test-table")
+            .set_key(Some(Item::builder().s("id", "x").build().into()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap();
+        assert_eq!(Item::try_from(fetched).unwrap(), item);
+
+        store
+            .bulk_insert("test-table", vec![Item::builder().s("id", "y").build()])
+            .unwrap();
+        assert_eq!(store.snapshot().items("test-table").unwrap().len(), 2);
+    }
+}
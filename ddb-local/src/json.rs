@@ -0,0 +1,204 @@
+//! Conversion between [`AttributeValue`] and DynamoDB JSON
+//!
+//! DynamoDB JSON tags every value with its type, e.g. `{"S": "hello"}` or
+//! `{"M": {"n": {"N": "1"}}}`. Numbers stay strings and binary values are
+//! base64-encoded, exactly as they appear on the wire.
+
+use aws_smithy_types::{Blob, base64};
+use dynamodb_local_server_sdk::model::AttributeValue;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Error returned when a JSON value is not valid DynamoDB JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAttributeValue(String);
+
+impl fmt::Display for InvalidAttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid DynamoDB JSON: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidAttributeValue {}
+
+fn invalid(message: impl Into<String>) -> InvalidAttributeValue {
+    InvalidAttributeValue(message.into())
+}
+
+/// Convert an attribute value to its DynamoDB JSON representation
+pub fn attribute_value_to_json(value: &AttributeValue) -> Value {
+    let (tag, inner) = match value {
+        AttributeValue::S(s) => ("S", Value::String(s.clone())),
+        AttributeValue::N(n) => ("N", Value::String(n.clone())),
+        AttributeValue::B(b) => ("B", Value::String(base64::encode(b.as_ref()))),
+        AttributeValue::Bool(b) => ("BOOL", Value::Bool(*b)),
+        AttributeValue::Null(b) => ("NULL", Value::Bool(*b)),
+        AttributeValue::Ss(set) => ("SS", set.iter().cloned().map(Value::String).collect()),
+        AttributeValue::Ns(set) => ("NS", set.iter().cloned().map(Value::String).collect()),
+        AttributeValue::Bs(set) => (
+            "BS",
+            set.iter()
+                .map(|b| Value::String(base64::encode(b.as_ref())))
+                .collect(),
+        ),
+        AttributeValue::L(list) => ("L", list.iter().map(attribute_value_to_json).collect()),
+        AttributeValue::M(map) => ("M", Value::Object(item_to_json(map))),
+    };
+    let mut object = Map::new();
+    object.insert(tag.to_string(), inner);
+    Value::Object(object)
+}
+
+/// Parse a DynamoDB JSON value into an attribute value
+pub fn json_to_attribute_value(value: &Value) -> Result<AttributeValue, InvalidAttributeValue> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| invalid(format!("expected an object, got {value}")))?;
+    let mut entries = object.iter();
+    let (tag, inner) = match (entries.next(), entries.next()) {
+        (Some(entry), None) => entry,
+        _ => {
+            return Err(invalid(format!(
+                "expected exactly one type tag, got {value}"
+            )));
+        }
+    };
+
+    Ok(match tag.as_str() {
+        "S" => AttributeValue::S(string(inner)?),
+        "N" => AttributeValue::N(string(inner)?),
+        "B" => AttributeValue::B(blob(inner)?),
+        "BOOL" => AttributeValue::Bool(boolean(inner)?),
+        "NULL" => AttributeValue::Null(boolean(inner)?),
+        "SS" => AttributeValue::Ss(array(inner)?.iter().map(string).collect::<Result<_, _>>()?),
+        "NS" => AttributeValue::Ns(array(inner)?.iter().map(string).collect::<Result<_, _>>()?),
+        "BS" => AttributeValue::Bs(array(inner)?.iter().map(blob).collect::<Result<_, _>>()?),
+        "L" => AttributeValue::L(
+            array(inner)?
+                .iter()
+                .map(json_to_attribute_value)
+                .collect::<Result<_, _>>()?,
+        ),
+        "M" => AttributeValue::M(json_to_item(inner)?),
+        other => return Err(invalid(format!("unknown type tag {other:?}"))),
+    })
+}
+
+/// Convert a whole item to a JSON object of DynamoDB JSON values
+pub fn item_to_json(item: &HashMap<String, AttributeValue>) -> Map<String, Value> {
+    item.iter()
+        .map(|(name, value)| (name.clone(), attribute_value_to_json(value)))
+        .collect()
+}
+
+/// Parse a JSON object of DynamoDB JSON values into an item
+pub fn json_to_item(
+    value: &Value,
+) -> Result<HashMap<String, AttributeValue>, InvalidAttributeValue> {
+    value
+        .as_object()
+        .ok_or_else(|| invalid(format!("expected an object, got {value}")))?
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), json_to_attribute_value(value)?)))
+        .collect()
+}
+
+fn string(value: &Value) -> Result<String, InvalidAttributeValue> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| invalid(format!("expected a string, got {value}")))
+}
+
+fn boolean(value: &Value) -> Result<bool, InvalidAttributeValue> {
+    value
+        .as_bool()
+        .ok_or_else(|| invalid(format!("expected a boolean, got {value}")))
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>, InvalidAttributeValue> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(format!("expected an array, got {value}")))
+}
+
+fn blob(value: &Value) -> Result<Blob, InvalidAttributeValue> {
+    let encoded = string(value)?;
+    base64::decode(&encoded)
+        .map(Blob::new)
+        .map_err(|_| invalid(format!("invalid base64 {encoded:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assert_round_trip(value: AttributeValue, expected: Value) {
+        let encoded = attribute_value_to_json(&value);
+        assert_eq!(encoded, expected);
+        assert_eq!(json_to_attribute_value(&encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn test_scalar_round_trips() {
+        assert_round_trip(AttributeValue::S("hi".to_string()), json!({"S": "hi"}));
+        assert_round_trip(AttributeValue::N("-1.5".to_string()), json!({"N": "-1.5"}));
+        assert_round_trip(
+            AttributeValue::B(Blob::new(b"\x00\x01".to_vec())),
+            json!({"B": "AAE="}),
+        );
+        assert_round_trip(AttributeValue::Bool(false), json!({"BOOL": false}));
+        assert_round_trip(AttributeValue::Null(true), json!({"NULL": true}));
+    }
+
+    #[test]
+    fn test_set_round_trips() {
+        assert_round_trip(
+            AttributeValue::Ss(vec!["a".to_string(), "b".to_string()]),
+            json!({"SS": ["a", "b"]}),
+        );
+        assert_round_trip(
+            AttributeValue::Ns(vec!["1".to_string(), "2".to_string()]),
+            json!({"NS": ["1", "2"]}),
+        );
+        assert_round_trip(
+            AttributeValue::Bs(vec![Blob::new(b"a".to_vec()), Blob::new(b"b".to_vec())]),
+            json!({"BS": ["YQ==", "Yg=="]}),
+        );
+    }
+
+    #[test]
+    fn test_nested_document_round_trips() {
+        let value = AttributeValue::M(HashMap::from([
+            (
+                "tags".to_string(),
+                AttributeValue::L(vec![
+                    AttributeValue::S("x".to_string()),
+                    AttributeValue::M(HashMap::from([(
+                        "depth".to_string(),
+                        AttributeValue::N("2".to_string()),
+                    )])),
+                ]),
+            ),
+            ("empty".to_string(), AttributeValue::L(vec![])),
+        ]));
+        assert_round_trip(
+            value,
+            json!({"M": {
+                "tags": {"L": [{"S": "x"}, {"M": {"depth": {"N": "2"}}}]},
+                "empty": {"L": []},
+            }}),
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_json() {
+        assert!(json_to_attribute_value(&json!("plain")).is_err());
+        assert!(json_to_attribute_value(&json!({"S": "a", "N": "1"})).is_err());
+        assert!(json_to_attribute_value(&json!({"X": "a"})).is_err());
+        assert!(json_to_attribute_value(&json!({"N": 1})).is_err());
+        assert!(json_to_attribute_value(&json!({"B": "not base64!"})).is_err());
+    }
+}
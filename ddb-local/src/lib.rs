@@ -5,17 +5,38 @@ use aws_smithy_runtime_api::client::http::{
 use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
 use aws_smithy_types::body::SdkBody;
+use connections::CountingListener;
 use dynamodb_local_server_sdk::server::body::BoxBody;
 use dynamodb_local_server_sdk::{error, input, output};
+use futures_util::FutureExt;
 use http::Uri;
 use http_body_util::BodyExt;
 use std::convert::Infallible;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Mutex;
-use tower::Service;
 use tower::util::BoxCloneService;
+use tower::{Service, ServiceExt};
 
 pub mod backend;
+mod body_limit;
+pub mod clock;
+mod connections;
+mod error_type;
+mod expression;
+mod fallback;
+pub mod fixture;
+pub mod item;
+pub mod json;
+pub mod recording;
+mod reserved_words;
+mod response_headers;
+pub mod snapshot;
+pub mod testing;
+#[cfg(feature = "tls")]
+pub mod tls;
+
+pub use fallback::FallbackDynamoDb;
 
 type DdbService = BoxCloneService<http::Request<SdkBody>, http::Response<BoxBody>, Infallible>;
 
@@ -38,6 +59,18 @@ impl InMemoryHttpClient {
             service: Arc::new(Mutex::new(service)),
         }
     }
+
+    /// `false` while a request holds the service, otherwise whether
+    /// `poll_ready` completes immediately
+    ///
+    /// `DdbService` can't fail (its error type is `Infallible`), so there is no
+    /// readiness error to report; this only tells "idle" from "busy".
+    fn is_ready(&self) -> bool {
+        let Ok(mut service) = self.service.try_lock() else {
+            return false;
+        };
+        matches!(service.ready().now_or_never(), Some(Ok(_)))
+    }
 }
 
 impl HttpConnector for InMemoryHttpClient {
@@ -52,9 +85,12 @@ impl HttpConnector for InMemoryHttpClient {
             // not sure why needed, but smithy rejects otherwise
             *http_req.uri_mut() = Uri::from_static("/");
 
-            // Call the service
+            // Call the service. Both patterns are irrefutable because the
+            // service's error type is `Infallible`: routing and backend errors
+            // come back as HTTP responses, never as `Err`.
             let mut svc = service.lock().await;
-            let response = svc.call(http_req).await.unwrap();
+            let Ok(svc) = svc.ready().await;
+            let Ok(response) = svc.call(http_req).await;
 
             // Convert http::Response to HttpResponse
             let (parts, body) = response.into_parts();
@@ -85,6 +121,13 @@ impl HttpClient for InMemoryHttpClient {
 }
 
 /// Trait for DynamoDB backend implementations
+///
+/// Implemented for `Arc<T>`, `Box<T>` and `&T` of any backend, so shared
+/// backends can be passed where a `DynamoDb` is expected. With this trait in
+/// scope, that means `create_table` on an `Arc<InMemoryDynamoDb>` resolves to
+/// the trait method, which takes a `CreateTableInput`, and a call to the
+/// inherent helper such as `backend.create_table("t", &["id"])` no longer
+/// compiles. Deref to reach the helper: `(*backend).create_table("t", &["id"])`.
 #[async_trait::async_trait]
 pub trait DynamoDb: Send + Sync {
     async fn get_item(
@@ -106,8 +149,58 @@ pub trait DynamoDb: Send + Sync {
         &self,
         input: input::UpdateItemInput,
     ) -> Result<output::UpdateItemOutput, error::UpdateItemError>;
+
+    /// Names of the operations this backend implements, e.g. `"GetItem"`
+    ///
+    /// Lets tooling and tests check for an operation before relying on it.
+    /// Defaults to the operations every backend must implement.
+    fn supported_operations(&self) -> &'static [&'static str] {
+        &["GetItem", "PutItem", "CreateTable", "UpdateItem"]
+    }
 }
 
+/// Implement [`DynamoDb`] for a pointer type by forwarding to its target
+macro_rules! forward_dynamodb {
+    ($($pointer:ty),*) => {$(
+        #[async_trait::async_trait]
+        impl<T: DynamoDb + ?Sized> DynamoDb for $pointer {
+            async fn get_item(
+                &self,
+                input: input::GetItemInput,
+            ) -> Result<output::GetItemOutput, error::GetItemError> {
+                (**self).get_item(input).await
+            }
+
+            async fn put_item(
+                &self,
+                input: input::PutItemInput,
+            ) -> Result<output::PutItemOutput, error::PutItemError> {
+                (**self).put_item(input).await
+            }
+
+            async fn create_table(
+                &self,
+                input: input::CreateTableInput,
+            ) -> Result<output::CreateTableOutput, error::CreateTableError> {
+                (**self).create_table(input).await
+            }
+
+            async fn update_item(
+                &self,
+                input: input::UpdateItemInput,
+            ) -> Result<output::UpdateItemOutput, error::UpdateItemError> {
+                (**self).update_item(input).await
+            }
+
+            fn supported_operations(&self) -> &'static [&'static str] {
+                (**self).supported_operations()
+            }
+        }
+    )*};
+}
+
+forward_dynamodb!(Arc<T>, Box<T>, &T);
+
 macro_rules! build_service {
     ($backend:expr) => {{
         use dynamodb_local_server_sdk::server::{
@@ -154,6 +247,12 @@ macro_rules! build_service {
 /// Builder for DynamoDB local server
 pub struct DynamoDbLocalBuilder {
     backend: Arc<dyn DynamoDb>,
+    max_request_bytes: usize,
+    shutdown_on_drop: bool,
+    aws_response_headers: bool,
+    path_prefix: Option<String>,
+    debug_endpoint: bool,
+    operation_timeout: Option<std::time::Duration>,
 }
 
 impl DynamoDbLocalBuilder {
@@ -161,6 +260,12 @@ impl DynamoDbLocalBuilder {
     pub fn new() -> Self {
         Self {
             backend: Arc::new(backend::InMemoryDynamoDb::new()),
+            max_request_bytes: body_limit::DEFAULT_MAX_REQUEST_BYTES,
+            shutdown_on_drop: false,
+            aws_response_headers: false,
+            path_prefix: None,
+            debug_endpoint: false,
+            operation_timeout: None,
         }
     }
 
@@ -170,48 +275,201 @@ impl DynamoDbLocalBuilder {
         self
     }
 
-    /// Bind to an automatically assigned port
-    pub async fn bind(self) -> std::io::Result<BoundDynamoDbLocal> {
-        use tokio::net::TcpListener;
+    /// Use a backend that is already shared, e.g. to serve the same data from
+    /// several servers or transports
+    pub fn with_shared_backend(mut self, backend: Arc<dyn DynamoDb>) -> Self {
+        self.backend = backend;
+        self
+    }
 
-        let app = build_service!(self.backend);
-        let listener = TcpListener::bind("127.0.0.1:0").await?;
-        let addr = listener.local_addr()?;
+    /// Reject request bodies larger than `max_bytes` on bound servers
+    ///
+    /// Oversized requests get a 413 before they are decoded. Defaults to
+    /// DynamoDB's 16MB request limit.
+    pub fn max_request_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_request_bytes = max_bytes;
+        self
+    }
 
-        tokio::spawn(async move {
-            let make_service = app.into_make_service();
-            dynamodb_local_server_sdk::serve(listener, make_service)
-                .await
-                .unwrap();
-        });
+    /// Stop bound servers when their [`BoundDynamoDbLocal`] is dropped
+    ///
+    /// The listener is closed right away, so the port is freed; in-flight
+    /// requests are allowed to finish. Off by default, in which case the server
+    /// runs until the runtime shuts down.
+    pub fn shutdown_on_drop(mut self, enabled: bool) -> Self {
+        self.shutdown_on_drop = enabled;
+        self
+    }
 
-        Ok(BoundDynamoDbLocal {
-            addr,
-            backend: self.backend,
-        })
+    /// Add the `x-amzn-RequestId` and `x-amz-crc32` headers real DynamoDB
+    /// sends to every response from bound servers
+    ///
+    /// For clients that log request IDs or validate the checksum. Off by
+    /// default, since computing the checksum buffers each response.
+    pub fn aws_response_headers(mut self, enabled: bool) -> Self {
+        self.aws_response_headers = enabled;
+        self
+    }
+
+    /// Serve bound servers under `prefix`, e.g. `/ddb`, for reverse proxies
+    /// that route by path
+    ///
+    /// The prefix is stripped before requests reach the DynamoDB service, and
+    /// requests outside it get a 404. [`BoundDynamoDbLocal::endpoint_url`]
+    /// includes the prefix. Leading and trailing slashes are optional: `ddb`,
+    /// `/ddb` and `/ddb/` are the same prefix.
+    pub fn with_path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let prefix = prefix.trim_matches('/');
+        self.path_prefix = (!prefix.is_empty()).then(|| format!("/{prefix}"));
+        self
+    }
+
+    /// Answer `GET /__debug/metrics` on bound servers with the number of
+    /// requests received per operation
+    ///
+    /// The response is JSON like `{"operations": {"PutItem": 2}}`, counting
+    /// every request that named an operation, whether or not it succeeded.
+    /// Off by default.
+    pub fn debug_endpoint(mut self, enabled: bool) -> Self {
+        self.debug_endpoint = enabled;
+        self
+    }
+
+    /// Fail requests to bound servers that take longer than `timeout` with a
+    /// 500 `InternalServerError`
+    ///
+    /// For testing client timeout and retry handling against a slow backend.
+    /// The backend call is dropped when the timeout fires; the in-memory
+    /// backend never holds its store lock across an await, so the lock is
+    /// released with it.
+    pub fn operation_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.operation_timeout = Some(timeout);
+        self
+    }
+
+    /// Bind to an automatically assigned port
+    pub async fn bind(self) -> std::io::Result<BoundDynamoDbLocal> {
+        self.bind_to_address(([127, 0, 0, 1], 0)).await
     }
 
     /// Bind to a specific address and start the server
+    ///
+    /// Besides the DynamoDB API, the server answers `GET /healthz` with a 200
+    /// for use as a readiness probe.
     pub async fn bind_to_address(
         self,
         addr: impl Into<std::net::SocketAddr>,
     ) -> std::io::Result<BoundDynamoDbLocal> {
-        use tokio::net::TcpListener;
+        let listener = tokio::net::TcpListener::bind(addr.into()).await?;
+        self.bind_with(listener, "http").await
+    }
 
-        let app = build_service!(self.backend);
-        let listener = TcpListener::bind(addr.into()).await?;
+    /// Bind to `addr` and serve over HTTPS with `tls_config`
+    ///
+    /// [`BoundDynamoDbLocal::endpoint_url`] then starts with `https://`. See
+    /// the [`tls`] module for a self-signed certificate to use in tests.
+    /// Requires the `tls` cargo feature.
+    #[cfg(feature = "tls")]
+    pub async fn bind_tls(
+        self,
+        addr: impl Into<std::net::SocketAddr>,
+        tls_config: Arc<tls::ServerConfig>,
+    ) -> std::io::Result<BoundDynamoDbLocal> {
+        let listener = tokio::net::TcpListener::bind(addr.into()).await?;
+        self.bind_with(tls::TlsListener::new(listener, tls_config)?, "https")
+            .await
+    }
+
+    async fn bind_with<L>(
+        self,
+        listener: L,
+        scheme: &'static str,
+    ) -> std::io::Result<BoundDynamoDbLocal>
+    where
+        L: dynamodb_local_server_sdk::server::serve::Listener<Addr = std::net::SocketAddr>,
+    {
+        use dynamodb_local_server_sdk::server::{body, routing::IntoMakeService};
+        use tower::{Layer, ServiceExt};
+
+        let app = response_headers::AwsResponseHeadersLayer::new(self.aws_response_headers).layer(
+            error_type::ErrorTypeLayer.layer(
+                body_limit::RequestBodyLimitLayer::new(self.max_request_bytes)
+                    .layer(build_service!(self.backend)),
+            ),
+        );
         let addr = listener.local_addr()?;
+        let active_connections = Arc::new(AtomicUsize::new(0));
+        let listener = CountingListener::new(listener, active_connections.clone());
 
+        let path_prefix = self.path_prefix.clone();
+        let operation_counts = self
+            .debug_endpoint
+            .then(|| Arc::new(std::sync::Mutex::new(OperationCounts::new())));
+        let service = tower::service_fn(move |mut req: http::Request<hyper::body::Incoming>| {
+            let app = app.clone();
+            let path_prefix = path_prefix.clone();
+            let operation_counts = operation_counts.clone();
+            let operation_timeout = self.operation_timeout;
+            async move {
+                if let Some(prefix) = &path_prefix {
+                    let Some(uri) = strip_path_prefix(req.uri(), prefix) else {
+                        let mut response = http::Response::new(body::empty());
+                        *response.status_mut() = http::StatusCode::NOT_FOUND;
+                        return Ok(response);
+                    };
+                    *req.uri_mut() = uri;
+                }
+                if req.method() == http::Method::GET && req.uri().path() == "/healthz" {
+                    return Ok(http::Response::new(body::empty()));
+                }
+                if let Some(counts) = &operation_counts {
+                    if req.method() == http::Method::GET && req.uri().path() == "/__debug/metrics" {
+                        return Ok(debug_metrics(&counts.lock().unwrap()));
+                    }
+                    if let Some(operation) = req
+                        .headers()
+                        .get("x-amz-target")
+                        .and_then(|target| target.to_str().ok())
+                        .and_then(|target| target.strip_prefix("DynamoDB_20120810."))
+                    {
+                        *counts
+                            .lock()
+                            .unwrap()
+                            .entry(operation.to_string())
+                            .or_default() += 1;
+                    }
+                }
+                match operation_timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, app.oneshot(req)).await {
+                        Ok(response) => response,
+                        Err(_) => Ok(timed_out(timeout)),
+                    },
+                    None => app.oneshot(req).await,
+                }
+            }
+        });
+
+        let (shutdown, shutdown_signal) = tokio::sync::oneshot::channel::<()>();
         tokio::spawn(async move {
-            let make_service = app.into_make_service();
-            dynamodb_local_server_sdk::serve(listener, make_service)
+            dynamodb_local_server_sdk::serve(listener, IntoMakeService::new(service))
+                .with_graceful_shutdown(async move {
+                    // A sender dropped without signalling means "never stop"
+                    if shutdown_signal.await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                })
                 .await
                 .unwrap();
         });
 
         Ok(BoundDynamoDbLocal {
             addr,
+            scheme,
+            path_prefix: self.path_prefix,
             backend: self.backend,
+            active_connections,
+            shutdown: self.shutdown_on_drop.then_some(shutdown),
         })
     }
 
@@ -234,10 +492,60 @@ impl Default for DynamoDbLocalBuilder {
     }
 }
 
+/// Requests received per operation name, for the debug endpoint
+type OperationCounts = std::collections::BTreeMap<String, u64>;
+
+fn debug_metrics(counts: &OperationCounts) -> http::Response<BoxBody> {
+    let body = serde_json::json!({ "operations": counts }).to_string();
+    http::Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(dynamodb_local_server_sdk::server::body::to_boxed(body))
+        .unwrap()
+}
+
+fn timed_out(timeout: std::time::Duration) -> http::Response<BoxBody> {
+    let body = format!(
+        r#"{{"__type":"com.amazonaws.dynamodb.v20120810#InternalServerError","message":"Operation timed out after {timeout:?}"}}"#
+    );
+    http::Response::builder()
+        .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+        .header(http::header::CONTENT_TYPE, "application/x-amz-json-1.0")
+        .body(dynamodb_local_server_sdk::server::body::to_boxed(body))
+        .unwrap()
+}
+
+/// `uri` with `prefix` removed from its path, or `None` if the path is
+/// outside `prefix`
+fn strip_path_prefix(uri: &Uri, prefix: &str) -> Option<Uri> {
+    let rest = uri.path().strip_prefix(prefix)?;
+    let rest = match rest {
+        "" => "/",
+        rest if rest.starts_with('/') => rest,
+        _ => return None,
+    };
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{rest}?{query}"),
+        None => rest.to_string(),
+    };
+    path_and_query.parse().ok()
+}
+
 /// DynamoDB local bound to a network address
 pub struct BoundDynamoDbLocal {
     addr: std::net::SocketAddr,
+    scheme: &'static str,
+    path_prefix: Option<String>,
     backend: Arc<dyn DynamoDb>,
+    active_connections: Arc<AtomicUsize>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Drop for BoundDynamoDbLocal {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
 }
 
 impl BoundDynamoDbLocal {
@@ -248,7 +556,12 @@ impl BoundDynamoDbLocal {
 
     /// Get the endpoint URL for this server
     pub fn endpoint_url(&self) -> String {
-        format!("http://{}", self.addr)
+        format!(
+            "{}://{}{}",
+            self.scheme,
+            self.addr,
+            self.path_prefix.as_deref().unwrap_or_default()
+        )
     }
 
     /// Get a reference to the backend
@@ -256,14 +569,54 @@ impl BoundDynamoDbLocal {
         &*self.backend
     }
 
+    /// Number of HTTP connections currently open to this server
+    ///
+    /// Useful for spotting connection leaks in client code.
+    pub fn active_connections(&self) -> usize {
+        self.active_connections.load(Ordering::SeqCst)
+    }
+
     /// Create a pre-configured AWS SDK client pointing to this server
+    ///
+    /// Credentials come from the `AWS_*` environment variables or the shared
+    /// profile files when they are set, and fall back to static test
+    /// credentials otherwise, since the server doesn't check them.
     pub async fn client(&self) -> aws_sdk_dynamodb::Client {
-        let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        aws_sdk_dynamodb::Client::new(&self.sdk_config().load().await)
+    }
+
+    /// Create a pre-configured AWS SDK client that sends requests through
+    /// `http_client`
+    ///
+    /// Needed for servers bound with `bind_tls` to a certificate the default
+    /// client doesn't trust, e.g. with the client from
+    /// `tls::SelfSignedCertificate::http_client` (`tls` feature).
+    pub async fn client_with_http_client(
+        &self,
+        http_client: SharedHttpClient,
+    ) -> aws_sdk_dynamodb::Client {
+        let config = self.sdk_config().http_client(http_client).load().await;
+        aws_sdk_dynamodb::Client::new(&config)
+    }
+
+    fn sdk_config(&self) -> aws_config::ConfigLoader {
+        aws_config::defaults(aws_config::BehaviorVersion::latest())
             .endpoint_url(self.endpoint_url())
             .region(aws_config::Region::new("us-east-1"))
-            .load()
-            .await;
-        aws_sdk_dynamodb::Client::new(&config)
+            .credentials_provider(
+                aws_config::meta::credentials::CredentialsProviderChain::first_try(
+                    "Environment",
+                    aws_config::environment::EnvironmentVariableCredentialsProvider::new(),
+                )
+                .or_else(
+                    "Profile",
+                    aws_config::profile::ProfileFileCredentialsProvider::builder().build(),
+                )
+                .or_else(
+                    "Static",
+                    aws_sdk_dynamodb::config::Credentials::new("test", "test", None, None, "test"),
+                ),
+            )
     }
 }
 
@@ -274,6 +627,17 @@ pub struct InMemoryDynamoDbLocal {
 }
 
 impl InMemoryDynamoDbLocal {
+    /// Serve `backend` over the in-memory transport
+    ///
+    /// Shorthand for `DynamoDbLocal::builder().with_backend(backend).as_http_client()`.
+    /// Keep a clone of the backend to seed or inspect data directly, and mint
+    /// as many clients as needed with [`client`](Self::client).
+    pub fn from_backend(backend: impl DynamoDb + 'static) -> Self {
+        DynamoDbLocal::builder()
+            .with_backend(backend)
+            .as_http_client()
+    }
+
     /// Get the HttpClient for manual configuration
     pub fn http_client(&self) -> SharedHttpClient {
         SharedHttpClient::new(self.http_client.clone())
@@ -284,14 +648,49 @@ impl InMemoryDynamoDbLocal {
         &*self.backend
     }
 
+    /// Whether the service can take a request right now
+    ///
+    /// The in-memory transport handles one request at a time, so this is
+    /// `false` while a request is in flight; a request sent now would wait.
+    /// Useful when working out why a test hangs.
+    ///
+    /// The service itself never fails to become ready (its error type is
+    /// `Infallible`), so this reports "no request in flight" rather than
+    /// health: failures show up as error responses to the request instead.
+    pub fn is_ready(&self) -> bool {
+        self.http_client.is_ready()
+    }
+
     /// Create a pre-configured AWS SDK client using the in-memory transport
+    ///
+    /// The client uses the SDK's test defaults: `us-east-1`, static test
+    /// credentials and a fixed time source.
     pub async fn client(&self) -> aws_sdk_dynamodb::Client {
-        let config = aws_sdk_dynamodb::Config::builder()
+        aws_sdk_dynamodb::Client::from_conf(self.config_builder().build())
+    }
+
+    /// Create an AWS SDK client using the in-memory transport with the given
+    /// region and credentials
+    ///
+    /// Everything else matches [`client`](Self::client).
+    pub async fn client_with(
+        &self,
+        region: aws_sdk_dynamodb::config::Region,
+        credentials: aws_sdk_dynamodb::config::Credentials,
+    ) -> aws_sdk_dynamodb::Client {
+        let config = self
+            .config_builder()
+            .region(region)
+            .credentials_provider(credentials)
+            .build();
+        aws_sdk_dynamodb::Client::from_conf(config)
+    }
+
+    fn config_builder(&self) -> aws_sdk_dynamodb::config::Builder {
+        aws_sdk_dynamodb::Config::builder()
             .http_client(SharedHttpClient::new(self.http_client.clone()))
             .with_test_defaults_v2()
             .behavior_version(BehaviorVersion::latest())
-            .build();
-        aws_sdk_dynamodb::Client::from_conf(config)
     }
 }
 
@@ -304,3 +703,775 @@ impl DynamoDbLocal {
         DynamoDbLocalBuilder::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::types::AttributeValue;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send a raw HTTP/1.1 request and return the full response text
+    async fn raw_http_request(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    /// POST a DynamoDB JSON request and return the status code and body
+    async fn post_json(addr: std::net::SocketAddr, operation: &str, body: &str) -> (u16, String) {
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nX-Amz-Target: DynamoDB_20120810.{operation}\r\nContent-Type: application/x-amz-json-1.0\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let response = raw_http_request(addr, &request).await;
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, body.to_string())
+    }
+
+    /// The error test backends return for operations a test never sends
+    fn not_used() -> error::InternalServerError {
+        error::InternalServerError {
+            message: Some("not used in this test".to_string()),
+        }
+    }
+
+    /// Backend whose reads always fail with an internal error
+    struct FailingBackend;
+
+    #[async_trait::async_trait]
+    impl DynamoDb for FailingBackend {
+        async fn get_item(
+            &self,
+            _input: input::GetItemInput,
+        ) -> Result<output::GetItemOutput, error::GetItemError> {
+            Err(error::GetItemError::InternalServerError(
+                error::InternalServerError {
+                    message: Some("boom".to_string()),
+                },
+            ))
+        }
+
+        async fn put_item(
+            &self,
+            _input: input::PutItemInput,
+        ) -> Result<output::PutItemOutput, error::PutItemError> {
+            Err(error::PutItemError::InternalServerError(not_used()))
+        }
+
+        async fn create_table(
+            &self,
+            _input: input::CreateTableInput,
+        ) -> Result<output::CreateTableOutput, error::CreateTableError> {
+            Err(error::CreateTableError::InternalServerError(not_used()))
+        }
+
+        async fn update_item(
+            &self,
+            _input: input::UpdateItemInput,
+        ) -> Result<output::UpdateItemOutput, error::UpdateItemError> {
+            Err(error::UpdateItemError::InternalServerError(not_used()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_request_bytes() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .max_request_bytes(200)
+            .bind()
+            .await
+            .unwrap();
+
+        let small = r#"{"TableName":"test-table","Item":{"id":{"S":"a"}}}"#;
+        let (status, body) = post_json(local.addr(), "PutItem", small).await;
+        assert_eq!(status, 200, "{body}");
+
+        let large = format!(
+            r#"{{"TableName":"test-table","Item":{{"id":{{"S":"{}"}}}}}}"#,
+            "x".repeat(500)
+        );
+        let (status, body) = post_json(local.addr(), "PutItem", &large).await;
+        assert_eq!(status, 413);
+        assert!(body.contains("ValidationException"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_max_request_bytes_chunked() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .max_request_bytes(200)
+            .bind()
+            .await
+            .unwrap();
+        let addr = local.addr();
+        let post_chunked = |body: String| {
+            let request = format!(
+                "POST / HTTP/1.1\r\nHost: localhost\r\nX-Amz-Target: DynamoDB_20120810.PutItem\r\nContent-Type: application/x-amz-json-1.0\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{body}\r\n0\r\n\r\n",
+                body.len()
+            );
+            async move { raw_http_request(addr, &request).await }
+        };
+
+        let response =
+            post_chunked(r#"{"TableName":"test-table","Item":{"id":{"S":"a"}}}"#.to_string()).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let large = format!(
+            r#"{{"TableName":"test-table","Item":{{"id":{{"S":"{}"}}}}}}"#,
+            "x".repeat(500)
+        );
+        let response = post_chunked(large).await;
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+        assert!(response.contains("ValidationException"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_aws_response_headers() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let request = r#"{"TableName":"test-table","Key":{"id":{"S":"a"}}}"#;
+        let header = |response: &str, name: &str| {
+            response
+                .split("\r\n\r\n")
+                .next()
+                .unwrap()
+                .lines()
+                .find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.eq_ignore_ascii_case(name)
+                        .then(|| value.trim().to_string())
+                })
+        };
+        let get_item = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nX-Amz-Target: DynamoDB_20120810.GetItem\r\nContent-Type: application/x-amz-json-1.0\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{request}",
+            request.len()
+        );
+
+        let plain = DynamoDbLocal::builder()
+            .with_backend(backend.clone())
+            .bind()
+            .await
+            .unwrap();
+        let response = raw_http_request(plain.addr(), &get_item).await;
+        assert_eq!(header(&response, "x-amzn-requestid"), None);
+
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .aws_response_headers(true)
+            .bind()
+            .await
+            .unwrap();
+        let response = raw_http_request(local.addr(), &get_item).await;
+        let request_id = header(&response, "x-amzn-requestid").unwrap();
+        assert!(!request_id.is_empty());
+        assert!(request_id.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let crc: u32 = header(&response, "x-amz-crc32").unwrap().parse().unwrap();
+        assert_eq!(crc, response_headers::crc32(body.as_bytes()));
+
+        // The SDK client still works against the decorated responses
+        let client = local.client().await;
+        client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_on_drop() {
+        let server = DynamoDbLocal::builder()
+            .shutdown_on_drop(true)
+            .bind()
+            .await
+            .unwrap();
+        let addr = server.addr();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok());
+
+        drop(server);
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(addr).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("server still accepting connections after its handle was dropped");
+    }
+
+    #[tokio::test]
+    async fn test_active_connections() {
+        let local = DynamoDbLocal::builder().bind().await.unwrap();
+        assert_eq!(local.active_connections(), 0);
+
+        let wait_for = |expected: usize| {
+            let local = &local;
+            async move {
+                for _ in 0..100 {
+                    if local.active_connections() == expected {
+                        return;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+                panic!(
+                    "expected {expected} connections, found {}",
+                    local.active_connections()
+                );
+            }
+        };
+
+        let mut first = tokio::net::TcpStream::connect(local.addr()).await.unwrap();
+        let mut second = tokio::net::TcpStream::connect(local.addr()).await.unwrap();
+        for stream in [&mut first, &mut second] {
+            stream
+                .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .await
+                .unwrap();
+        }
+        wait_for(2).await;
+
+        drop(first);
+        drop(second);
+        wait_for(0).await;
+    }
+
+    #[tokio::test]
+    async fn test_path_prefix() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .with_path_prefix("/ddb/")
+            .bind()
+            .await
+            .unwrap();
+        assert_eq!(local.endpoint_url(), format!("http://{}/ddb", local.addr()));
+
+        let client = local.client().await;
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item;
+        assert!(item.is_some());
+
+        let addr = local.addr();
+        let health = |path: &str| {
+            let request =
+                format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+            async move { raw_http_request(addr, &request).await }
+        };
+        let response = health("/ddb/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = health("/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+        let response = health("/ddbx/healthz").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{response}");
+
+        // The leading slash is optional
+        let local = DynamoDbLocal::builder()
+            .with_path_prefix("ddb")
+            .bind()
+            .await
+            .unwrap();
+        assert_eq!(local.endpoint_url(), format!("http://{}/ddb", local.addr()));
+        let request = "GET /ddb/healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+        let response = raw_http_request(local.addr(), request).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_bind_tls() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let certificate = tls::SelfSignedCertificate::generate();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .bind_tls(([127, 0, 0, 1], 0), certificate.server_config())
+            .await
+            .unwrap();
+        assert_eq!(local.endpoint_url(), format!("https://{}", local.addr()));
+
+        // A client that connects but never starts its handshake doesn't hold
+        // up anyone else
+        let _silent = tokio::net::TcpStream::connect(local.addr()).await.unwrap();
+
+        let client = local
+            .client_with_http_client(certificate.http_client())
+            .await;
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let item = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item;
+        assert!(item.is_some());
+
+        // A client that doesn't trust the certificate can't connect
+        let untrusting = local
+            .client_with_http_client(tls::SelfSignedCertificate::generate().http_client())
+            .await;
+        let result = untrusting
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_debug_metrics_endpoint() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend.clone())
+            .debug_endpoint(true)
+            .bind()
+            .await
+            .unwrap();
+        let client = local.client().await;
+        for id in ["a", "b"] {
+            client
+                .put_item()
+                .table_name("test-table")
+                .item("id", AttributeValue::S(id.to_string()))
+                .send()
+                .await
+                .unwrap();
+        }
+        client
+            .get_item()
+            .table_name("missing-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap_err();
+
+        let metrics = |addr| async move {
+            let request =
+                "GET /__debug/metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+            raw_http_request(addr, request).await
+        };
+        let response = metrics(local.addr()).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            serde_json::json!({ "operations": { "PutItem": 2, "GetItem": 1 } })
+        );
+
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .bind()
+            .await
+            .unwrap();
+        let response = metrics(local.addr()).await;
+        assert!(!response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_healthz() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .bind()
+            .await
+            .unwrap();
+
+        let response = raw_http_request(
+            local.addr(),
+            "GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let client = local.client().await;
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let result = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        assert!(result.item.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_shared_backend_across_transports() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let shared: Arc<dyn DynamoDb> = Arc::new(backend);
+
+        let bound = DynamoDbLocal::builder()
+            .with_shared_backend(shared.clone())
+            .bind()
+            .await
+            .unwrap();
+        let in_memory = DynamoDbLocal::builder()
+            .with_shared_backend(shared)
+            .as_http_client();
+
+        bound
+            .client()
+            .await
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+
+        let result = in_memory
+            .client()
+            .await
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        assert!(result.item.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_map_to_400() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .bind()
+            .await
+            .unwrap();
+
+        let cases = [
+            (
+                "GetItem",
+                r#"{"TableName":"missing","Key":{"id":{"S":"x"}}}"#,
+                "ResourceNotFoundException",
+            ),
+            (
+                "PutItem",
+                r#"{"TableName":"test-table","Item":{"id":{"S":"x"}},"ConditionExpression":"attribute_exists(id)"}"#,
+                "ConditionalCheckFailedException",
+            ),
+            (
+                "PutItem",
+                r#"{"TableName":"test-table","Item":{"id":{"S":"x"}},"ConditionExpression":"id ="}"#,
+                "ValidationException",
+            ),
+        ];
+
+        for (operation, body, error_type) in cases {
+            let (status, body) = post_json(local.addr(), operation, body).await;
+            assert_eq!(status, 400, "{operation}: {body}");
+            assert!(
+                body.contains(&format!("#{error_type}\"")),
+                "expected {error_type} in {body}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_body_is_json_1_0() {
+        let local = DynamoDbLocal::builder().bind().await.unwrap();
+
+        let (status, body) = post_json(
+            local.addr(),
+            "GetItem",
+            r#"{"TableName":"missing","Key":{"id":{"S":"x"}}}"#,
+        )
+        .await;
+        assert_eq!(status, 400, "{body}");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "__type": "com.amazonaws.dynamodb.v20120810#ResourceNotFoundException",
+                "message": "Requested resource not found: Table: missing not found",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_internal_errors_map_to_500() {
+        let local = DynamoDbLocal::builder()
+            .with_backend(FailingBackend)
+            .bind()
+            .await
+            .unwrap();
+
+        let (status, body) = post_json(
+            local.addr(),
+            "GetItem",
+            r#"{"TableName":"test-table","Key":{"id":{"S":"x"}}}"#,
+        )
+        .await;
+        assert_eq!(status, 500, "{body}");
+        assert!(body.contains("#InternalServerError\""), "{body}");
+
+        let err = local
+            .client()
+            .await
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("x".to_string()))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.into_service_error(),
+            aws_sdk_dynamodb::operation::get_item::GetItemError::InternalServerError(_)
+        ));
+    }
+
+    /// Backend whose reads wait until `release` is notified
+    struct BlockingBackend {
+        release: Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl DynamoDb for BlockingBackend {
+        async fn get_item(
+            &self,
+            _input: input::GetItemInput,
+        ) -> Result<output::GetItemOutput, error::GetItemError> {
+            self.release.notified().await;
+            Ok(output::GetItemOutput {
+                item: None,
+                consumed_capacity: None,
+            })
+        }
+
+        async fn put_item(
+            &self,
+            _input: input::PutItemInput,
+        ) -> Result<output::PutItemOutput, error::PutItemError> {
+            Err(error::PutItemError::InternalServerError(not_used()))
+        }
+
+        async fn create_table(
+            &self,
+            _input: input::CreateTableInput,
+        ) -> Result<output::CreateTableOutput, error::CreateTableError> {
+            Err(error::CreateTableError::InternalServerError(not_used()))
+        }
+
+        async fn update_item(
+            &self,
+            _input: input::UpdateItemInput,
+        ) -> Result<output::UpdateItemOutput, error::UpdateItemError> {
+            Err(error::UpdateItemError::InternalServerError(not_used()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operation_timeout() {
+        let local = DynamoDbLocal::builder()
+            .with_backend(BlockingBackend {
+                release: Arc::new(tokio::sync::Notify::new()),
+            })
+            .operation_timeout(std::time::Duration::from_millis(50))
+            .bind()
+            .await
+            .unwrap();
+
+        let (status, body) = post_json(
+            local.addr(),
+            "GetItem",
+            r#"{"TableName":"test-table","Key":{"id":{"S":"a"}}}"#,
+        )
+        .await;
+        assert_eq!(status, 500, "{body}");
+        assert!(body.contains("InternalServerError"), "{body}");
+
+        let client = local.client().await;
+        let result = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await;
+        crate::testing::assert_error_code(result, "InternalServerError");
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_readiness() {
+        let release = Arc::new(tokio::sync::Notify::new());
+        let local = DynamoDbLocal::builder()
+            .with_backend(BlockingBackend {
+                release: release.clone(),
+            })
+            .as_http_client();
+        assert!(local.is_ready());
+
+        let client = local.client().await;
+        let request = tokio::spawn(async move {
+            client
+                .get_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("a".to_string()))
+                .send()
+                .await
+        });
+        while local.is_ready() {
+            tokio::task::yield_now().await;
+        }
+
+        release.notify_one();
+        request.await.unwrap().unwrap();
+        assert!(local.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_arc_backend() {
+        let backend = Arc::new(backend::InMemoryDynamoDb::new());
+        // The trait's create_table shadows the helper on the Arc itself
+        (*backend).create_table("test-table", &["id"]).unwrap();
+        let client = DynamoDbLocal::builder()
+            .with_backend(backend.clone())
+            .as_http_client()
+            .client()
+            .await;
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("a".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let pk = dynamodb_local_server_sdk::model::AttributeValue::S("a".to_string());
+        assert_eq!(backend.query_partition("test-table", pk).len(), 1);
+    }
+
+    #[test]
+    fn test_supported_operations() {
+        let backend = backend::InMemoryDynamoDb::new();
+        let operations = backend.supported_operations();
+        for operation in ["GetItem", "PutItem", "CreateTable", "UpdateItem"] {
+            assert!(operations.contains(&operation), "{operation}");
+        }
+        assert!(!operations.contains(&"Query"));
+        assert_eq!(Arc::new(backend).supported_operations(), operations);
+    }
+
+    #[tokio::test]
+    async fn test_from_seeded_backend() {
+        use dynamodb_local_server_sdk::model::AttributeValue as ServerValue;
+
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        backend
+            .bulk_insert(
+                "test-table",
+                vec![HashMap::from([(
+                    "id".to_string(),
+                    ServerValue::S("seeded".to_string()),
+                )])],
+            )
+            .unwrap();
+
+        let local = InMemoryDynamoDbLocal::from_backend(backend.clone());
+        for client in [local.client().await, local.client().await] {
+            let item = client
+                .get_item()
+                .table_name("test-table")
+                .key("id", AttributeValue::S("seeded".to_string()))
+                .send()
+                .await
+                .unwrap()
+                .item;
+            assert!(item.is_some());
+        }
+        assert_eq!(
+            backend
+                .query_partition("test-table", ServerValue::S("seeded".to_string()))
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_client_with_region() {
+        let backend = backend::InMemoryDynamoDb::new();
+        backend.create_table("test-table", &["id"]).unwrap();
+        let local = DynamoDbLocal::builder()
+            .with_backend(backend)
+            .as_http_client();
+
+        let client = local
+            .client_with(
+                aws_sdk_dynamodb::config::Region::new("eu-west-1"),
+                aws_sdk_dynamodb::config::Credentials::new("akid", "secret", None, None, "test"),
+            )
+            .await;
+        assert_eq!(client.config().region().unwrap().as_ref(), "eu-west-1");
+
+        // Apart from region and credentials, both keep the SDK's test defaults
+        let default_client = local.client().await;
+        assert_eq!(
+            default_client.config().region().unwrap().as_ref(),
+            "us-east-1"
+        );
+        let now = |client: &aws_sdk_dynamodb::Client| client.config().time_source().unwrap().now();
+        assert_eq!(now(&client), now(&default_client));
+        assert!(
+            now(&client) < std::time::SystemTime::now() - std::time::Duration::from_secs(86400)
+        );
+
+        client
+            .put_item()
+            .table_name("test-table")
+            .item("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        let result = client
+            .get_item()
+            .table_name("test-table")
+            .key("id", AttributeValue::S("test-id".to_string()))
+            .send()
+            .await
+            .unwrap();
+        assert!(result.item.is_some());
+    }
+}
@@ -40,6 +40,8 @@ async fn main() {
         .expect("failed to bind server");
 
     tracing::info!("server listening on {}", local.addr());
+    // Machine-readable so harnesses using `--port 0` can find the real port
+    println!("ENDPOINT=http://{}", local.addr());
 
     // Keep the server running
     tokio::signal::ctrl_c()
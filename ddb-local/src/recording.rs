@@ -0,0 +1,547 @@
+//! Recording the operations a backend receives, to replay them elsewhere
+//!
+//! Start a recording with [`InMemoryDynamoDb::record`], then hand the captured
+//! operations (or their JSON form) to [`InMemoryDynamoDb::replay`] on a fresh
+//! backend to reproduce the same sequence. Only API operations are recorded;
+//! state seeded with helpers such as `bulk_insert` must be recreated first.
+//!
+//! [`InMemoryDynamoDb::record`]: crate::backend::InMemoryDynamoDb::record
+//! [`InMemoryDynamoDb::replay`]: crate::backend::InMemoryDynamoDb::replay
+
+use crate::json::{item_to_json, json_to_item};
+use dynamodb_local_server_sdk::{error, input, model, output};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// An operation received by a backend, with its full input
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    GetItem(input::GetItemInput),
+    PutItem(input::PutItemInput),
+    CreateTable(input::CreateTableInput),
+    UpdateItem(input::UpdateItemInput),
+}
+
+/// What replaying one [`Operation`] returned
+///
+/// Returned by [`InMemoryDynamoDb::replay`], one per operation and in the same
+/// order.
+///
+/// [`InMemoryDynamoDb::replay`]: crate::backend::InMemoryDynamoDb::replay
+#[derive(Debug)]
+pub enum Outcome {
+    GetItem(Result<output::GetItemOutput, error::GetItemError>),
+    PutItem(Result<output::PutItemOutput, error::PutItemError>),
+    /// Boxed because a table description is much larger than the others
+    CreateTable(Box<Result<output::CreateTableOutput, error::CreateTableError>>),
+    UpdateItem(Result<output::UpdateItemOutput, error::UpdateItemError>),
+}
+
+impl Outcome {
+    /// Whether the operation succeeded
+    pub fn is_ok(&self) -> bool {
+        match self {
+            Outcome::GetItem(result) => result.is_ok(),
+            Outcome::PutItem(result) => result.is_ok(),
+            Outcome::CreateTable(result) => result.is_ok(),
+            Outcome::UpdateItem(result) => result.is_ok(),
+        }
+    }
+}
+
+/// Error returned when JSON is not a valid recorded operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRecording(String);
+
+impl fmt::Display for InvalidRecording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid recording: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidRecording {}
+
+fn invalid(message: impl fmt::Display) -> InvalidRecording {
+    InvalidRecording(message.to_string())
+}
+
+/// Handle to a recording started with [`InMemoryDynamoDb::record`]
+///
+/// [`InMemoryDynamoDb::record`]: crate::backend::InMemoryDynamoDb::record
+#[derive(Debug, Clone, Default)]
+pub struct RecordingHandle {
+    operations: Arc<Mutex<Vec<Operation>>>,
+}
+
+impl RecordingHandle {
+    pub(crate) fn push(&self, operation: Operation) {
+        self.operations.lock().unwrap().push(operation);
+    }
+
+    /// The operations recorded so far, in the order they were received
+    pub fn operations(&self) -> Vec<Operation> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    /// The operations recorded so far as a JSON array, for sharing
+    ///
+    /// Read it back with [`operations_from_json`].
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.operations().iter().map(Operation::to_json).collect())
+    }
+}
+
+/// Parse a JSON array produced by [`RecordingHandle::to_json`]
+pub fn operations_from_json(value: &Value) -> Result<Vec<Operation>, InvalidRecording> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(format!("expected an array, got {value}")))?
+        .iter()
+        .map(Operation::from_json)
+        .collect()
+}
+
+impl Operation {
+    /// Convert to JSON shaped like the request DynamoDB would receive
+    ///
+    /// Only parameters that affect what gets stored are kept: table names,
+    /// keys, items, expressions, `AttributeUpdates`, and table definitions
+    /// including billing mode, throughput and secondary indexes. Settings such
+    /// as `ReturnValues`, stream and encryption specifications and tags are
+    /// dropped.
+    pub fn to_json(&self) -> Value {
+        let mut fields = Map::new();
+        let name = match self {
+            Operation::GetItem(input) => {
+                fields.insert("TableName".into(), input.table_name.clone().into());
+                fields.insert("Key".into(), item_to_json(&input.key).into());
+                insert_string(
+                    &mut fields,
+                    "ProjectionExpression",
+                    &input.projection_expression,
+                );
+                insert_names(&mut fields, &input.expression_attribute_names);
+                "GetItem"
+            }
+            Operation::PutItem(input) => {
+                fields.insert("TableName".into(), input.table_name.clone().into());
+                fields.insert("Item".into(), item_to_json(&input.item).into());
+                insert_string(
+                    &mut fields,
+                    "ConditionExpression",
+                    &input.condition_expression,
+                );
+                insert_names(&mut fields, &input.expression_attribute_names);
+                insert_values(&mut fields, &input.expression_attribute_values);
+                "PutItem"
+            }
+            Operation::CreateTable(input) => {
+                fields.insert("TableName".into(), input.table_name.clone().into());
+                fields.insert("KeySchema".into(), key_schema_to_json(&input.key_schema));
+                let definitions = input
+                    .attribute_definitions
+                    .iter()
+                    .map(|definition| {
+                        serde_json::json!({
+                            "AttributeName": definition.attribute_name,
+                            "AttributeType": definition.attribute_type.as_str(),
+                        })
+                    })
+                    .collect();
+                fields.insert("AttributeDefinitions".into(), Value::Array(definitions));
+                if let Some(billing_mode) = &input.billing_mode {
+                    fields.insert("BillingMode".into(), billing_mode.as_str().into());
+                }
+                if let Some(throughput) = &input.provisioned_throughput {
+                    fields.insert(
+                        "ProvisionedThroughput".into(),
+                        throughput_to_json(throughput),
+                    );
+                }
+                if let Some(indexes) = &input.local_secondary_indexes {
+                    let indexes = indexes
+                        .iter()
+                        .map(|index| {
+                            serde_json::json!({
+                                "IndexName": index.index_name,
+                                "KeySchema": key_schema_to_json(&index.key_schema),
+                                "Projection": projection_to_json(&index.projection),
+                            })
+                        })
+                        .collect();
+                    fields.insert("LocalSecondaryIndexes".into(), Value::Array(indexes));
+                }
+                if let Some(indexes) = &input.global_secondary_indexes {
+                    let indexes = indexes
+                        .iter()
+                        .map(|index| {
+                            let mut fields = Map::new();
+                            fields.insert("IndexName".into(), index.index_name.clone().into());
+                            fields
+                                .insert("KeySchema".into(), key_schema_to_json(&index.key_schema));
+                            fields
+                                .insert("Projection".into(), projection_to_json(&index.projection));
+                            if let Some(throughput) = &index.provisioned_throughput {
+                                fields.insert(
+                                    "ProvisionedThroughput".into(),
+                                    throughput_to_json(throughput),
+                                );
+                            }
+                            Value::Object(fields)
+                        })
+                        .collect();
+                    fields.insert("GlobalSecondaryIndexes".into(), Value::Array(indexes));
+                }
+                "CreateTable"
+            }
+            Operation::UpdateItem(input) => {
+                fields.insert("TableName".into(), input.table_name.clone().into());
+                fields.insert("Key".into(), item_to_json(&input.key).into());
+                insert_string(&mut fields, "UpdateExpression", &input.update_expression);
+                insert_string(
+                    &mut fields,
+                    "ConditionExpression",
+                    &input.condition_expression,
+                );
+                insert_names(&mut fields, &input.expression_attribute_names);
+                insert_values(&mut fields, &input.expression_attribute_values);
+                if let Some(updates) = &input.attribute_updates {
+                    let updates = updates
+                        .iter()
+                        .map(|(name, update)| {
+                            let mut fields = Map::new();
+                            if let Some(action) = &update.action {
+                                fields.insert("Action".into(), action.as_str().into());
+                            }
+                            if let Some(value) = &update.value {
+                                fields.insert(
+                                    "Value".into(),
+                                    crate::json::attribute_value_to_json(value),
+                                );
+                            }
+                            (name.clone(), Value::Object(fields))
+                        })
+                        .collect();
+                    fields.insert("AttributeUpdates".into(), Value::Object(updates));
+                }
+                "UpdateItem"
+            }
+        };
+        serde_json::json!({ "Operation": name, "Input": fields })
+    }
+
+    /// Parse the JSON form produced by [`to_json`](Self::to_json)
+    pub fn from_json(value: &Value) -> Result<Self, InvalidRecording> {
+        let name = value
+            .get("Operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("missing Operation"))?;
+        let fields = value
+            .get("Input")
+            .filter(|fields| fields.is_object())
+            .ok_or_else(|| invalid("missing Input"))?;
+        let table_name = required(string(fields, "TableName")?, "TableName")?;
+
+        Ok(match name {
+            "GetItem" => Operation::GetItem(
+                input::GetItemInput::builder()
+                    .table_name(table_name)
+                    .key(required(item(fields, "Key")?, "Key")?)
+                    .projection_expression(string(fields, "ProjectionExpression")?)
+                    .expression_attribute_names(names(fields)?)
+                    .build()
+                    .map_err(invalid)?,
+            ),
+            "PutItem" => Operation::PutItem(
+                input::PutItemInput::builder()
+                    .table_name(table_name)
+                    .item(required(item(fields, "Item")?, "Item")?)
+                    .condition_expression(string(fields, "ConditionExpression")?)
+                    .expression_attribute_names(names(fields)?)
+                    .expression_attribute_values(item(fields, "ExpressionAttributeValues")?)
+                    .build()
+                    .map_err(invalid)?,
+            ),
+            "CreateTable" => {
+                let attribute_definitions = array(fields, "AttributeDefinitions")?
+                    .iter()
+                    .map(|definition| {
+                        Ok(model::AttributeDefinition {
+                            attribute_name: required(
+                                string(definition, "AttributeName")?,
+                                "AttributeName",
+                            )?,
+                            attribute_type: required(
+                                string(definition, "AttributeType")?,
+                                "AttributeType",
+                            )?
+                            .parse()
+                            .map_err(invalid)?,
+                        })
+                    })
+                    .collect::<Result<_, InvalidRecording>>()?;
+                let local_secondary_indexes = optional_array(fields, "LocalSecondaryIndexes")?
+                    .map(|indexes| {
+                        indexes
+                            .iter()
+                            .map(|index| {
+                                Ok(model::LocalSecondaryIndex {
+                                    index_name: required(string(index, "IndexName")?, "IndexName")?,
+                                    key_schema: key_schema(index)?,
+                                    projection: projection(index)?,
+                                })
+                            })
+                            .collect::<Result<_, InvalidRecording>>()
+                    })
+                    .transpose()?;
+                let global_secondary_indexes = optional_array(fields, "GlobalSecondaryIndexes")?
+                    .map(|indexes| {
+                        indexes
+                            .iter()
+                            .map(|index| {
+                                Ok(model::GlobalSecondaryIndex {
+                                    index_name: required(string(index, "IndexName")?, "IndexName")?,
+                                    key_schema: key_schema(index)?,
+                                    projection: projection(index)?,
+                                    provisioned_throughput: throughput(index)?,
+                                })
+                            })
+                            .collect::<Result<_, InvalidRecording>>()
+                    })
+                    .transpose()?;
+                Operation::CreateTable(
+                    input::CreateTableInput::builder()
+                        .table_name(table_name)
+                        .key_schema(key_schema(fields)?)
+                        .attribute_definitions(attribute_definitions)
+                        .billing_mode(
+                            string(fields, "BillingMode")?
+                                .map(|mode| mode.parse().map_err(invalid))
+                                .transpose()?,
+                        )
+                        .provisioned_throughput(throughput(fields)?)
+                        .local_secondary_indexes(local_secondary_indexes)
+                        .global_secondary_indexes(global_secondary_indexes)
+                        .build()
+                        .map_err(invalid)?,
+                )
+            }
+            "UpdateItem" => Operation::UpdateItem(
+                input::UpdateItemInput::builder()
+                    .table_name(table_name)
+                    .key(required(item(fields, "Key")?, "Key")?)
+                    .update_expression(string(fields, "UpdateExpression")?)
+                    .condition_expression(string(fields, "ConditionExpression")?)
+                    .expression_attribute_names(names(fields)?)
+                    .expression_attribute_values(item(fields, "ExpressionAttributeValues")?)
+                    .attribute_updates(attribute_updates(fields)?)
+                    .build()
+                    .map_err(invalid)?,
+            ),
+            other => return Err(invalid(format!("unknown operation {other}"))),
+        })
+    }
+}
+
+fn insert_string(fields: &mut Map<String, Value>, name: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        fields.insert(name.into(), value.clone().into());
+    }
+}
+
+fn insert_names(fields: &mut Map<String, Value>, names: &Option<HashMap<String, String>>) {
+    if let Some(names) = names {
+        let names = names
+            .iter()
+            .map(|(placeholder, name)| (placeholder.clone(), name.clone().into()))
+            .collect();
+        fields.insert("ExpressionAttributeNames".into(), Value::Object(names));
+    }
+}
+
+fn insert_values(
+    fields: &mut Map<String, Value>,
+    values: &Option<HashMap<String, model::AttributeValue>>,
+) {
+    if let Some(values) = values {
+        fields.insert(
+            "ExpressionAttributeValues".into(),
+            item_to_json(values).into(),
+        );
+    }
+}
+
+fn key_schema_to_json(key_schema: &[model::KeySchemaElement]) -> Value {
+    key_schema
+        .iter()
+        .map(|key| {
+            serde_json::json!({
+                "AttributeName": key.attribute_name,
+                "KeyType": key.key_type.as_str(),
+            })
+        })
+        .collect()
+}
+
+fn throughput_to_json(throughput: &model::ProvisionedThroughput) -> Value {
+    serde_json::json!({
+        "ReadCapacityUnits": throughput.read_capacity_units,
+        "WriteCapacityUnits": throughput.write_capacity_units,
+    })
+}
+
+fn projection_to_json(projection: &model::Projection) -> Value {
+    let mut fields = Map::new();
+    if let Some(projection_type) = &projection.projection_type {
+        fields.insert("ProjectionType".into(), projection_type.as_str().into());
+    }
+    if let Some(attributes) = &projection.non_key_attributes {
+        fields.insert("NonKeyAttributes".into(), attributes.clone().into());
+    }
+    Value::Object(fields)
+}
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T, InvalidRecording> {
+    value.ok_or_else(|| invalid(format!("missing {name}")))
+}
+
+fn string(value: &Value, name: &str) -> Result<Option<String>, InvalidRecording> {
+    value
+        .get(name)
+        .map(|field| {
+            field
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(format!("{name} must be a string")))
+        })
+        .transpose()
+}
+
+fn array<'a>(fields: &'a Value, name: &str) -> Result<&'a Vec<Value>, InvalidRecording> {
+    fields
+        .get(name)
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid(format!("{name} must be an array")))
+}
+
+fn optional_array<'a>(
+    fields: &'a Value,
+    name: &str,
+) -> Result<Option<&'a Vec<Value>>, InvalidRecording> {
+    fields
+        .get(name)
+        .map(|value| {
+            value
+                .as_array()
+                .ok_or_else(|| invalid(format!("{name} must be an array")))
+        })
+        .transpose()
+}
+
+fn key_schema(fields: &Value) -> Result<Vec<model::KeySchemaElement>, InvalidRecording> {
+    array(fields, "KeySchema")?
+        .iter()
+        .map(|key| {
+            Ok(model::KeySchemaElement {
+                attribute_name: required(string(key, "AttributeName")?, "AttributeName")?,
+                key_type: required(string(key, "KeyType")?, "KeyType")?
+                    .parse()
+                    .map_err(invalid)?,
+            })
+        })
+        .collect()
+}
+
+fn throughput(fields: &Value) -> Result<Option<model::ProvisionedThroughput>, InvalidRecording> {
+    let Some(throughput) = fields.get("ProvisionedThroughput") else {
+        return Ok(None);
+    };
+    let units = |name: &str| {
+        throughput
+            .get(name)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| invalid(format!("{name} must be an integer")))
+    };
+    Ok(Some(model::ProvisionedThroughput {
+        read_capacity_units: units("ReadCapacityUnits")?,
+        write_capacity_units: units("WriteCapacityUnits")?,
+    }))
+}
+
+fn projection(fields: &Value) -> Result<model::Projection, InvalidRecording> {
+    let projection = fields
+        .get("Projection")
+        .ok_or_else(|| invalid("missing Projection"))?;
+    let non_key_attributes = optional_array(projection, "NonKeyAttributes")?
+        .map(|attributes| {
+            attributes
+                .iter()
+                .map(|attribute| {
+                    attribute
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| invalid("NonKeyAttributes must be strings"))
+                })
+                .collect::<Result<_, _>>()
+        })
+        .transpose()?;
+    Ok(model::Projection {
+        projection_type: string(projection, "ProjectionType")?
+            .map(|projection_type| projection_type.parse().map_err(invalid))
+            .transpose()?,
+        non_key_attributes,
+    })
+}
+
+fn item(
+    fields: &Value,
+    name: &str,
+) -> Result<Option<HashMap<String, model::AttributeValue>>, InvalidRecording> {
+    fields
+        .get(name)
+        .map(|value| json_to_item(value).map_err(invalid))
+        .transpose()
+}
+
+fn names(fields: &Value) -> Result<Option<HashMap<String, String>>, InvalidRecording> {
+    let Some(names) = fields.get("ExpressionAttributeNames") else {
+        return Ok(None);
+    };
+    names
+        .as_object()
+        .ok_or_else(|| invalid("ExpressionAttributeNames must be an object"))?
+        .iter()
+        .map(|(placeholder, name)| {
+            name.as_str()
+                .map(|name| (placeholder.clone(), name.to_string()))
+                .ok_or_else(|| invalid("ExpressionAttributeNames values must be strings"))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn attribute_updates(
+    fields: &Value,
+) -> Result<Option<HashMap<String, model::AttributeValueUpdate>>, InvalidRecording> {
+    let Some(updates) = fields.get("AttributeUpdates") else {
+        return Ok(None);
+    };
+    updates
+        .as_object()
+        .ok_or_else(|| invalid("AttributeUpdates must be an object"))?
+        .iter()
+        .map(|(name, update)| {
+            let action = string(update, "Action")?
+                .map(|action| action.parse().map_err(invalid))
+                .transpose()?;
+            let value = update
+                .get("Value")
+                .map(|value| crate::json::json_to_attribute_value(value).map_err(invalid))
+                .transpose()?;
+            Ok((name.clone(), model::AttributeValueUpdate { value, action }))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
@@ -0,0 +1,106 @@
+use dynamodb_local_server_sdk::server::body::{self, BoxBody};
+use http::HeaderValue;
+use http_body_util::BodyExt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Adds the `x-amzn-RequestId` and `x-amz-crc32` headers real DynamoDB sends
+///
+/// The response body is buffered to compute its checksum. When disabled the
+/// layer passes responses through untouched.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AwsResponseHeadersLayer {
+    enabled: bool,
+}
+
+impl AwsResponseHeadersLayer {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+impl<S> Layer<S> for AwsResponseHeadersLayer {
+    type Service = AwsResponseHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AwsResponseHeaders {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AwsResponseHeaders<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S, R> Service<R> for AwsResponseHeaders<S>
+where
+    S: Service<R, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let response = self.inner.call(req);
+        if !self.enabled {
+            return Box::pin(response);
+        }
+
+        Box::pin(async move {
+            let (mut parts, body) = response.await?.into_parts();
+            let bytes = body
+                .collect()
+                .await
+                .map(|collected| collected.to_bytes())
+                .unwrap_or_default();
+
+            let request_id = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+            parts.headers.insert(
+                "x-amzn-requestid",
+                HeaderValue::from_str(&request_id).expect("request IDs are ASCII"),
+            );
+            parts
+                .headers
+                .insert("x-amz-crc32", HeaderValue::from(crc32(&bytes)));
+            Ok(http::Response::from_parts(parts, body::to_boxed(bytes)))
+        })
+    }
+}
+
+/// CRC-32 (IEEE 802.3), the checksum DynamoDB puts in `x-amz-crc32`
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
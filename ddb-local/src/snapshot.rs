@@ -0,0 +1,154 @@
+//! Point-in-time copies of the in-memory store, for asserting on mutations
+//!
+//! Take a [`StoreSnapshot`] with [`InMemoryDynamoDb::snapshot`] before and
+//! after the code under test, then [`diff`](StoreSnapshot::diff) them to see
+//! exactly which tables and items changed.
+//!
+//! [`InMemoryDynamoDb::snapshot`]: crate::backend::InMemoryDynamoDb::snapshot
+
+use crate::json::item_to_json;
+use dynamodb_local_server_sdk::model::AttributeValue;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+type Item = HashMap<String, AttributeValue>;
+
+/// Every table and item in a store at the moment it was taken
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreSnapshot {
+    /// Items of each table, keyed by their encoded primary key
+    pub(crate) tables: BTreeMap<String, BTreeMap<Vec<String>, Item>>,
+}
+
+impl StoreSnapshot {
+    /// Names of the tables in the snapshot, sorted
+    pub fn table_names(&self) -> Vec<String> {
+        self.tables.keys().cloned().collect()
+    }
+
+    /// Items of `table_name` in a stable order, or `None` if it didn't exist
+    pub fn items(&self, table_name: &str) -> Option<Vec<Item>> {
+        self.tables
+            .get(table_name)
+            .map(|items| items.values().cloned().collect())
+    }
+
+    /// What changed going from `self` to `other`
+    ///
+    /// Items are matched by primary key. Only tables present in both snapshots
+    /// get a per-item diff; the items of added or removed tables are not listed.
+    pub fn diff(&self, other: &StoreSnapshot) -> StoreDiff {
+        let mut diff = StoreDiff::default();
+        for (table_name, before) in &self.tables {
+            let Some(after) = other.tables.get(table_name) else {
+                diff.tables_removed.push(table_name.clone());
+                continue;
+            };
+            let mut table = TableDiff::default();
+            for (key, old) in before {
+                match after.get(key) {
+                    None => table.removed.push(old.clone()),
+                    Some(new) if new != old => table.changed.push((old.clone(), new.clone())),
+                    Some(_) => {}
+                }
+            }
+            for (key, new) in after {
+                if !before.contains_key(key) {
+                    table.added.push(new.clone());
+                }
+            }
+            if !table.is_empty() {
+                diff.tables.insert(table_name.clone(), table);
+            }
+        }
+        diff.tables_added = other
+            .tables
+            .keys()
+            .filter(|table_name| !self.tables.contains_key(*table_name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// Differences between two [`StoreSnapshot`]s, from [`StoreSnapshot::diff`]
+///
+/// The `Display` form lists one change per line, prefixed with `+` for
+/// additions, `-` for removals and `~` for changes, which reads well in a
+/// failed assertion.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreDiff {
+    pub tables_added: Vec<String>,
+    pub tables_removed: Vec<String>,
+    /// Item changes per table, only for tables that changed
+    pub tables: BTreeMap<String, TableDiff>,
+}
+
+impl StoreDiff {
+    /// Whether the two snapshots were identical
+    pub fn is_empty(&self) -> bool {
+        self.tables_added.is_empty() && self.tables_removed.is_empty() && self.tables.is_empty()
+    }
+}
+
+/// Item changes within one table, each list in a stable order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDiff {
+    pub added: Vec<Item>,
+    pub removed: Vec<Item>,
+    /// Items whose attributes changed, as `(before, after)`
+    pub changed: Vec<(Item, Item)>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for StoreDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for table_name in &self.tables_added {
+            writeln!(f, "+ table {table_name}")?;
+        }
+        for table_name in &self.tables_removed {
+            writeln!(f, "- table {table_name}")?;
+        }
+        for (table_name, table) in &self.tables {
+            writeln!(f, "table {table_name}:")?;
+            for item in &table.added {
+                writeln!(f, "  + {}", json(item))?;
+            }
+            for item in &table.removed {
+                writeln!(f, "  - {}", json(item))?;
+            }
+            for (before, after) in &table.changed {
+                writeln!(f, "  ~ {} -> {}", json(before), json(after))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The item as DynamoDB JSON, with attributes sorted so output is stable
+fn json(item: &Item) -> Value {
+    sorted(Value::Object(item_to_json(item)))
+}
+
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let fields: BTreeMap<_, _> = fields
+                .into_iter()
+                .map(|(name, value)| (name, sorted(value)))
+                .collect();
+            Value::Object(fields.into_iter().collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sorted).collect()),
+        other => other,
+    }
+}
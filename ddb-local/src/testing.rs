@@ -0,0 +1,44 @@
+//! Assertions for checking the errors returned by the AWS SDK client
+//!
+//! Each helper accepts either the raw `Result` from `.send().await` or an
+//! already-unwrapped service error, and panics with the actual outcome if the
+//! expected error wasn't returned.
+
+use aws_sdk_dynamodb::error::ProvideErrorMetadata;
+use std::fmt::Debug;
+
+/// Assert that `result` failed with the error code `code`
+#[track_caller]
+pub fn assert_error_code<T: Debug, E: ProvideErrorMetadata + Debug>(
+    result: Result<T, E>,
+    code: &str,
+) {
+    match result {
+        Ok(output) => panic!("expected {code}, but the request succeeded: {output:?}"),
+        Err(err) => {
+            if err.code() != Some(code) {
+                panic!("expected {code}, got: {err:?}");
+            }
+        }
+    }
+}
+
+/// Assert that `result` failed with `ConditionalCheckFailedException`
+#[track_caller]
+pub fn assert_conditional_check_failed<T: Debug, E: ProvideErrorMetadata + Debug>(
+    result: Result<T, E>,
+) {
+    assert_error_code(result, "ConditionalCheckFailedException");
+}
+
+/// Assert that `result` failed with `ResourceNotFoundException`
+#[track_caller]
+pub fn assert_resource_not_found<T: Debug, E: ProvideErrorMetadata + Debug>(result: Result<T, E>) {
+    assert_error_code(result, "ResourceNotFoundException");
+}
+
+/// Assert that `result` failed with `ValidationException`
+#[track_caller]
+pub fn assert_validation_error<T: Debug, E: ProvideErrorMetadata + Debug>(result: Result<T, E>) {
+    assert_error_code(result, "ValidationException");
+}
@@ -0,0 +1,170 @@
+//! Serving over HTTPS, for client code that refuses plain HTTP endpoints
+//!
+//! Requires the `tls` cargo feature.
+//!
+//! Bind with [`DynamoDbLocalBuilder::bind_tls`] using any rustls
+//! [`ServerConfig`]. For tests, [`SelfSignedCertificate`] provides both a
+//! server config and an HTTP client that trusts it:
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! use ddb_local::DynamoDbLocal;
+//! use ddb_local::tls::SelfSignedCertificate;
+//!
+//! let certificate = SelfSignedCertificate::generate();
+//! let local = DynamoDbLocal::builder()
+//!     .bind_tls(([127, 0, 0, 1], 0), certificate.server_config())
+//!     .await?;
+//! let client = local.client_with_http_client(certificate.http_client()).await;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DynamoDbLocalBuilder::bind_tls`]: crate::DynamoDbLocalBuilder::bind_tls
+
+use aws_smithy_http_client::tls::{self, TlsContext, TrustStore, rustls_provider::CryptoMode};
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
+use dynamodb_local_server_sdk::server::serve::Listener;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+
+pub use rustls::ServerConfig;
+
+/// A certificate for `localhost` and `127.0.0.1`, signed by itself
+#[derive(Debug, Clone)]
+pub struct SelfSignedCertificate {
+    certificate_pem: String,
+    certificate_der: CertificateDer<'static>,
+    /// PKCS#8 DER of the private key
+    key_der: Vec<u8>,
+}
+
+impl SelfSignedCertificate {
+    /// Generate a fresh certificate and key pair
+    pub fn generate() -> Self {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(["localhost".to_string(), "127.0.0.1".to_string()])
+                .expect("self-signed certificate parameters are valid");
+        Self {
+            certificate_pem: cert.pem(),
+            certificate_der: cert.der().clone(),
+            key_der: key_pair.serialize_der(),
+        }
+    }
+
+    /// The certificate in PEM form, e.g. to add to another client's trust store
+    pub fn certificate_pem(&self) -> &str {
+        &self.certificate_pem
+    }
+
+    /// A server config presenting this certificate, for
+    /// [`bind_tls`](crate::DynamoDbLocalBuilder::bind_tls)
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        let config = ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::aws_lc_rs::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .expect("the default protocol versions are supported")
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![self.certificate_der.clone()],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(self.key_der.clone())),
+        )
+        .expect("a freshly generated key matches its certificate");
+        Arc::new(config)
+    }
+
+    /// An HTTPS client for the AWS SDK that trusts only this certificate
+    pub fn http_client(&self) -> SharedHttpClient {
+        let trust_store = TrustStore::empty()
+            .with_native_roots(false)
+            .with_pem_certificate(self.certificate_pem.as_bytes());
+        aws_smithy_http_client::Builder::new()
+            .tls_provider(tls::Provider::Rustls(CryptoMode::AwsLc))
+            .tls_context(
+                TlsContext::builder()
+                    .with_trust_store(trust_store)
+                    .build()
+                    .expect("the trust store holds a valid certificate"),
+            )
+            .build_https()
+    }
+}
+
+/// How long a client gets to complete its TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections waiting to be picked up by the server
+const ACCEPT_BACKLOG: usize = 32;
+
+/// Listener that completes a TLS handshake on each accepted connection
+///
+/// A background task accepts connections and runs each handshake in its own
+/// task, so a client that connects and never sends a ClientHello only ties up
+/// its own connection. Handshakes that fail or take longer than
+/// [`HANDSHAKE_TIMEOUT`] are dropped.
+pub(crate) struct TlsListener {
+    local_addr: SocketAddr,
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    accept_task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    pub(crate) fn new(listener: TcpListener, config: Arc<ServerConfig>) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(config);
+        let (sender, connections) = mpsc::channel(ACCEPT_BACKLOG);
+        let accept_task = tokio::spawn(async move {
+            let mut listener = listener;
+            loop {
+                let (io, addr) = Listener::accept(&mut listener).await;
+                let (acceptor, sender) = (acceptor.clone(), sender.clone());
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(io)).await {
+                        Ok(Ok(io)) => {
+                            // The server has shut down if nobody is receiving
+                            let _ = sender.send((io, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!("TLS handshake with {addr} failed: {e}"),
+                        Err(_) => tracing::debug!("TLS handshake with {addr} timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            local_addr,
+            connections,
+            accept_task,
+        })
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        self.connections
+            .recv()
+            .await
+            .expect("the accept task runs until the listener is dropped")
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{BufRead, BufReader};
+use std::net::{SocketAddr, TcpStream};
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_port_zero_prints_endpoint() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ddb-local"))
+        .args(["--port", "0"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn ddb-local");
+
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let endpoint = stdout
+        .lines()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("ENDPOINT=").map(str::to_string));
+
+    let endpoint = endpoint.expect("no ENDPOINT= line on stdout");
+    let addr: SocketAddr = endpoint.strip_prefix("http://").unwrap().parse().unwrap();
+    assert_ne!(addr.port(), 0);
+    let connected = TcpStream::connect(addr);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    connected.expect("server is not accepting connections");
+}